@@ -18,13 +18,15 @@
 #![no_std]
 
 mod asset_id;
+mod network;
 mod processing;
 mod sampling;
 mod sanity;
 mod serdes;
 
 pub use asset_id::{AssetId, TEST_ASSET};
-pub use processing::Process;
+pub use network::{NetworkId, TEST_NETWORK};
+pub use processing::{CommitmentVersion, Process};
 pub use sampling::Sampling;
 pub use sanity::SanityCheck;
 #[cfg(test)]
@@ -107,6 +109,7 @@ pub struct MantaAssetReceiverSpendingInfo {
 /// A MantaAssetShieldedAddress is the data that a receiver use to receive utxo
 /// from a sender. The sender will be able to build a `MantaAssetProcessedReceiver`
 /// with this information.
+/// - the identifier of the network this address is for
 /// - the identifier of this asset type
 /// - some public information k
 /// - some public information s
@@ -114,6 +117,8 @@ pub struct MantaAssetReceiverSpendingInfo {
 /// - ecpk: a ECIES public key for the sender to transmit `value` privately
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct MantaAssetShieldedAddress {
+	// network id
+	pub network_id: NetworkId,
 	// asset id
 	pub asset_id: AssetId,
 	pub k: [u8; 32],
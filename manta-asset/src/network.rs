@@ -0,0 +1,24 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements NetworkIds.
+
+/// A network id is a u32 that identifies the network, e.g., a testnet
+/// or a mainnet, that a transaction or a shielded address is for.
+pub type NetworkId = u32;
+
+/// The default value is 0 and is for testing only
+pub const TEST_NETWORK: NetworkId = 0;
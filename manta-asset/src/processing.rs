@@ -35,20 +35,55 @@ pub trait Process {
 	) -> Result<Self::Output, MantaError>;
 }
 
+/// The format of the commitment of a processed receiver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommitmentVersion {
+	/// `cm = com(v||k, s)`, the format of the published proving and
+	/// verifying keys, which the circuits of `manta-circuits` reject.
+	V0,
+	/// `cm = com( asset_id | v||k, s )`, as for a sampled `MantaAsset`.
+	/// This is the format that the circuits of `manta-circuits` check,
+	/// and the one of `Process::process`.
+	V1,
+}
+
 impl Process for MantaAssetShieldedAddress {
 	type Param = CommitmentParam;
 	type Output = MantaAssetProcessedReceiver;
 	type Value = u64;
 
-	// process a prepared information to a processed information
+	// process a prepared information to a processed information,
+	// with a commitment in the format of the circuits, `V1`
 	fn process<R: RngCore + CryptoRng>(
 		&self,
 		value: &Self::Value,
 		rng: &mut R,
 	) -> Result<Self::Output, MantaError> {
+		self.process_with_version(value, CommitmentVersion::V1, rng)
+	}
+}
+
+impl MantaAssetShieldedAddress {
+	/// Process a prepared information to a processed information,
+	/// with a commitment in the format of `version`.
+	pub fn process_with_version<R: RngCore + CryptoRng>(
+		&self,
+		value: &u64,
+		version: CommitmentVersion,
+		rng: &mut R,
+	) -> Result<MantaAssetProcessedReceiver, MantaError> {
 		let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data)?;
-		// cm = com(v||k, s)
-		let buf: Vec<u8> = [value.to_le_bytes().as_ref(), self.k.as_ref()].concat();
+		let buf: Vec<u8> = match version {
+			// cm = com(v||k, s)
+			CommitmentVersion::V0 => [value.to_le_bytes().as_ref(), self.k.as_ref()].concat(),
+			// cm = com( asset_id | v||k, s )
+			CommitmentVersion::V1 => [
+				(self.asset_id as u64).to_le_bytes().as_ref(),
+				value.to_le_bytes().as_ref(),
+				self.k.as_ref(),
+			]
+			.concat(),
+		};
 		let commitment = <MantaCrypto as Commitment>::commit(&commit_param, &buf, &self.s)?;
 
 		let cipher = <MantaCrypto as Ecies>::encrypt(&self.ecpk, value, rng);
@@ -136,7 +136,8 @@ impl Sampling for MantaAssetFullReceiver {
 	type Value = ();
 	type AssetId = AssetId;
 
-	/// sampling a random asset
+	/// sampling a random asset, for the default network id;
+	/// see `sample_for_network`
 	/// - param: the parameter for generating the commitment
 	/// - secret_key: self-explained
 	/// - value: since this is a prepared receiver, we don't know the value it will hold
@@ -147,6 +148,24 @@ impl Sampling for MantaAssetFullReceiver {
 		asset_id: &AssetId,
 		_value: &Self::Value,
 		rng: &mut R,
+	) -> Result<Self, MantaError> {
+		Self::sample_for_network(param, secret_key, asset_id, &NetworkId::default(), rng)
+	}
+}
+
+impl MantaAssetFullReceiver {
+	/// sampling a random receiver, whose shielded address is for a network
+	/// - param: the parameter for generating the commitment
+	/// - secret_key: self-explained
+	/// - asset_id: self-explained
+	/// - network_id: the network the shielded address is for
+	/// - rng: self-explained
+	pub fn sample_for_network<R: RngCore + CryptoRng>(
+		param: &CommitmentParam,
+		secret_key: &[u8; 32],
+		asset_id: &AssetId,
+		network_id: &NetworkId,
+		rng: &mut R,
 	) -> Result<Self, MantaError> {
 		//  sample a random rho
 		let mut rho = [0u8; 32];
@@ -179,6 +198,7 @@ impl Sampling for MantaAssetFullReceiver {
 
 		Ok(MantaAssetFullReceiver {
 			shielded_address: MantaAssetShieldedAddress {
+				network_id: *network_id,
 				asset_id: *asset_id,
 				k: k_bytes,
 				s: s_bytes,
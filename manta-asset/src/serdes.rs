@@ -102,9 +102,31 @@ impl MantaSerDes for MantaAssetFullReceiver {
 	}
 	/// Deserialize a readable data into a struct.
 	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
+		let shielded_address = MantaAssetShieldedAddress::deserialize(&mut reader)?;
+		Self::read_spending_info(shielded_address, reader)
+	}
+}
+
+impl MantaAssetFullReceiver {
+	/// Deserialize a receiver whose shielded address is in the legacy
+	/// encoding, which has no network id, as a receiver for `network_id`.
+	pub fn deserialize_legacy<R: Read>(
+		mut reader: R,
+		network_id: NetworkId,
+	) -> Result<Self, MantaError> {
+		let shielded_address =
+			MantaAssetShieldedAddress::deserialize_legacy(&mut reader, network_id)?;
+		Self::read_spending_info(shielded_address, reader)
+	}
+
+	/// Read the spending info of `shielded_address`, and check the receiver.
+	fn read_spending_info<R: Read>(
+		shielded_address: MantaAssetShieldedAddress,
+		reader: R,
+	) -> Result<Self, MantaError> {
 		let receiver = MantaAssetFullReceiver {
-			shielded_address: MantaAssetShieldedAddress::deserialize(&mut reader)?,
-			spending_info: MantaAssetReceiverSpendingInfo::deserialize(&mut reader)?,
+			shielded_address,
+			spending_info: MantaAssetReceiverSpendingInfo::deserialize(reader)?,
 		};
 
 		let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data)?;
@@ -148,6 +170,7 @@ impl MantaSerDes for MantaAssetReceiverSpendingInfo {
 impl MantaSerDes for MantaAssetShieldedAddress {
 	/// Serialize a struct into a writable blob.
 	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
+		writer.write_all(&self.network_id.to_le_bytes())?;
 		writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
 
 		writer.write_all(&self.k)?;
@@ -157,7 +180,23 @@ impl MantaSerDes for MantaAssetShieldedAddress {
 	}
 	/// Deserialize a readable data into a struct.
 	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
-		let mut receiver = Self::default();
+		let mut buf = [0u8; 4];
+		reader.read_exact(buf.as_mut())?;
+		Self::deserialize_legacy(reader, u32::from_le_bytes(buf))
+	}
+}
+
+impl MantaAssetShieldedAddress {
+	/// Deserialize a shielded address in the legacy encoding, which has
+	/// no network id, as an address for `network_id`.
+	pub fn deserialize_legacy<R: Read>(
+		mut reader: R,
+		network_id: NetworkId,
+	) -> Result<Self, MantaError> {
+		let mut receiver = Self {
+			network_id,
+			..Default::default()
+		};
 
 		let mut buf = [0u8; 8];
 		reader.read_exact(buf.as_mut())?;
@@ -16,7 +16,7 @@
 
 use super::*;
 use ark_std::{rand::RngCore, vec::Vec};
-use manta_crypto::{CommitmentParam, MantaSerDes, COMMIT_PARAM};
+use manta_crypto::{Commitment, CommitmentParam, MantaCrypto, MantaSerDes, COMMIT_PARAM};
 
 #[test]
 fn test_manta_random_asset_serdes() {
@@ -43,11 +43,93 @@ fn test_manta_random_receiver_serdes() {
 	let mut secret_key = [0u8; 32];
 	rng.fill_bytes(&mut secret_key);
 
-	let random_receiver =
-		MantaAssetFullReceiver::sample(&commit_param, &secret_key, &asset_id, &(), &mut rng)
-			.unwrap();
+	let random_receiver = MantaAssetFullReceiver::sample_for_network(
+		&commit_param,
+		&secret_key,
+		&asset_id,
+		&TEST_NETWORK,
+		&mut rng,
+	)
+	.unwrap();
 	let mut buf: Vec<u8> = Vec::new();
 	random_receiver.serialize(&mut buf).unwrap();
 	let random_receiver_recover = MantaAssetFullReceiver::deserialize(buf.as_ref()).unwrap();
 	assert_eq!(random_receiver, random_receiver_recover);
 }
+
+#[test]
+fn test_shielded_address_network() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let mut secret_key = [0u8; 32];
+	rng.fill_bytes(&mut secret_key);
+
+	let receiver = MantaAssetFullReceiver::sample_for_network(
+		&commit_param,
+		&secret_key,
+		&TEST_ASSET,
+		&42,
+		&mut rng,
+	)
+	.unwrap();
+	let mut buf: Vec<u8> = Vec::new();
+	receiver.shielded_address.serialize(&mut buf).unwrap();
+	assert_eq!(buf[..4], 42u32.to_le_bytes());
+	let address = MantaAssetShieldedAddress::deserialize(buf.as_ref()).unwrap();
+	assert_eq!(address.network_id, 42);
+	assert_eq!(address, receiver.shielded_address);
+
+	// the legacy encoding has no network id, and is decoded for a given one
+	let legacy = MantaAssetShieldedAddress::deserialize_legacy(&buf[4..], 42).unwrap();
+	assert_eq!(legacy, receiver.shielded_address);
+	let mut buf: Vec<u8> = Vec::new();
+	receiver.serialize(&mut buf).unwrap();
+	let legacy = MantaAssetFullReceiver::deserialize_legacy(&buf[4..], 42).unwrap();
+	assert_eq!(legacy, receiver);
+
+	// and the default sampling is for the default network id
+	let receiver =
+		MantaAssetFullReceiver::sample(&commit_param, &secret_key, &TEST_ASSET, &(), &mut rng)
+			.unwrap();
+	assert_eq!(receiver.shielded_address.network_id, NetworkId::default());
+}
+
+#[test]
+fn test_commitment_version() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let mut secret_key = [0u8; 32];
+	rng.fill_bytes(&mut secret_key);
+	let address = MantaAssetFullReceiver::sample_for_network(
+		&commit_param,
+		&secret_key,
+		&TEST_ASSET,
+		&TEST_NETWORK,
+		&mut rng,
+	)
+	.unwrap()
+	.shielded_address;
+	let value = 10u64;
+
+	// `V0` is the format of the published keys
+	let v0 = address
+		.process_with_version(&value, CommitmentVersion::V0, &mut rng)
+		.unwrap();
+	let buf: Vec<u8> = [value.to_le_bytes().as_ref(), address.k.as_ref()].concat();
+	let cm = <MantaCrypto as Commitment>::commit(&commit_param, &buf, &address.s).unwrap();
+	assert_eq!(v0.utxo, cm);
+
+	// while `V1`, the format of `process`, also commits to the asset id
+	let v1 = address
+		.process_with_version(&value, CommitmentVersion::V1, &mut rng)
+		.unwrap();
+	let buf: Vec<u8> = [
+		(TEST_ASSET as u64).to_le_bytes().as_ref(),
+		value.to_le_bytes().as_ref(),
+		address.k.as_ref(),
+	]
+	.concat();
+	let cm = <MantaCrypto as Commitment>::commit(&commit_param, &buf, &address.s).unwrap();
+	assert_eq!(v1.utxo, cm);
+	assert_eq!(address.process(&value, &mut rng).unwrap().utxo, cm);
+}
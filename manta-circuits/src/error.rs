@@ -0,0 +1,57 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module defines the errors raised by the key setup tooling.
+
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use manta_data::{Arity, VerificationError};
+
+/// An error that is raised when generating keys, or reading a key file.
+#[derive(Debug)]
+pub enum SetupError {
+	/// The circuit could not be synthesized.
+	Synthesis(SynthesisError),
+	/// A key could not be (de)serialized, or the key file could not be written.
+	Serialization(SerializationError),
+	/// The file does not start with the key file magic bytes.
+	BadMagic,
+	/// The file has a format version that this crate does not read.
+	UnsupportedVersion(u16),
+	/// The file is for a circuit that this crate does not know about.
+	UnknownCircuit(u8),
+	/// Keys of this arity cannot be generated, or written to a key file.
+	UnsupportedArity(Arity),
+	/// The verifying key of the file is rejected by `VerificationKeys`,
+	/// e.g., it does not have the public inputs of the arity of the file.
+	Verification(VerificationError),
+	/// The length of the file does not match the lengths of its sections.
+	LengthMismatch,
+	/// The checksum does not match the content of the file.
+	ChecksumMismatch,
+}
+
+impl From<SynthesisError> for SetupError {
+	fn from(e: SynthesisError) -> Self {
+		SetupError::Synthesis(e)
+	}
+}
+
+impl From<SerializationError> for SetupError {
+	fn from(e: SerializationError) -> Self {
+		SetupError::Serialization(e)
+	}
+}
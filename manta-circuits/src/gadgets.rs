@@ -0,0 +1,372 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the gadgets shared by the transfer and
+//! reclaim circuits.
+
+use ark_crypto_primitives::{
+	commitment::pedersen::{
+		constraints::CommGadget, Commitment as PedersenCommitment, Randomness, Window,
+	},
+	crh::pedersen::{constraints::CRHGadget, CRH as PedersenCRH},
+	merkle_tree::{Config, MerkleTree as ArkMerkleTree},
+	prf::{blake2s::constraints::Blake2sGadget, Blake2s, PRFGadget},
+	CommitmentGadget, FixedLengthCRHGadget,
+};
+use ark_ed_on_bls12_381::{constraints::EdwardsVar, EdwardsProjective, Fq, Fr};
+use ark_ff::{BitIteratorLE, FpParameters, PrimeField};
+use ark_r1cs_std::{
+	alloc::{AllocVar, AllocationMode},
+	bits::{ToBitsGadget, ToBytesGadget},
+	boolean::Boolean,
+	eq::EqGadget,
+	fields::{fp::FpVar, FieldVar},
+	uint8::UInt8,
+	R1CSVar,
+};
+use ark_relations::{
+	ns,
+	r1cs::{ConstraintSystemRef, Namespace, SynthesisError},
+};
+use ark_serialize::CanonicalDeserialize;
+use ark_std::{borrow::Borrow, vec::Vec};
+use manta_asset::MantaAssetProcessedReceiver;
+use manta_crypto::{CommitmentScheme, LedgerMerkleTree};
+use manta_data::{MembershipPath, SenderMetaData};
+
+/// Recover the window of a Pedersen commitment or hash.
+pub trait PedersenWindow {
+	type Window: Window;
+}
+
+impl<W: Window> PedersenWindow for PedersenCommitment<EdwardsProjective, W> {
+	type Window = W;
+}
+
+impl<W: Window> PedersenWindow for PedersenCRH<EdwardsProjective, W> {
+	type Window = W;
+}
+
+/// Recover the merkle tree configuration from the `LedgerMerkleTree` alias.
+pub trait TreeConfig {
+	type Config: Config;
+}
+
+impl<P: Config> TreeConfig for ArkMerkleTree<P> {
+	type Config = P;
+}
+
+pub type LedgerTreeConfig = <LedgerMerkleTree as TreeConfig>::Config;
+pub type LedgerHash = <LedgerTreeConfig as Config>::H;
+
+/// The gadget of `CommitmentScheme`.
+pub type CommitmentSchemeVar =
+	CommGadget<EdwardsProjective, EdwardsVar, <CommitmentScheme as PedersenWindow>::Window>;
+
+/// The commitment parameters, as a circuit variable.
+pub type CommitmentParamVar =
+	<CommitmentSchemeVar as CommitmentGadget<CommitmentScheme, Fq>>::ParametersVar;
+
+type CommitmentRandomnessVar =
+	<CommitmentSchemeVar as CommitmentGadget<CommitmentScheme, Fq>>::RandomnessVar;
+
+/// The gadget of the hash function of the ledger merkle trees.
+pub type LedgerHashVar =
+	CRHGadget<EdwardsProjective, EdwardsVar, <LedgerHash as PedersenWindow>::Window>;
+
+/// The hash parameters, as a circuit variable.
+pub type LedgerHashParamVar =
+	<LedgerHashVar as FixedLengthCRHGadget<LedgerHash, Fq>>::ParametersVar;
+
+/// The gadget of `MembershipPath`, which checks a path as the arkworks
+/// `PathVar` checks an arkworks `Path`.
+pub struct MembershipPathVar {
+	path: Vec<(EdwardsVar, EdwardsVar)>,
+}
+
+impl AllocVar<MembershipPath, Fq> for MembershipPathVar {
+	fn new_variable<T: Borrow<MembershipPath>>(
+		cs: impl Into<Namespace<Fq>>,
+		f: impl FnOnce() -> Result<T, SynthesisError>,
+		mode: AllocationMode,
+	) -> Result<Self, SynthesisError> {
+		let ns = cs.into();
+		let cs = ns.cs();
+		let mut path = Vec::new();
+		for (left, right) in f()?.borrow().path() {
+			path.push((
+				EdwardsVar::new_variable(ns!(cs, "left"), || Ok(left), mode)?,
+				EdwardsVar::new_variable(ns!(cs, "right"), || Ok(right), mode)?,
+			));
+		}
+		Ok(Self { path })
+	}
+}
+
+impl MembershipPathVar {
+	/// Whether `leaf` is the leaf of the path, and the path leads to `root`.
+	pub fn check_membership(
+		&self,
+		hash_param: &LedgerHashParamVar,
+		root: &EdwardsVar,
+		leaf: &[UInt8<Fq>],
+	) -> Result<Boolean<Fq>, SynthesisError> {
+		let leaf_hash =
+			<LedgerHashVar as FixedLengthCRHGadget<LedgerHash, Fq>>::evaluate(hash_param, leaf)?;
+		let cs = leaf_hash.cs().or(root.cs());
+
+		// the leaf is one of the bottom-most nodes
+		let (left, right) = &self.path[0];
+		let leaf_is_left = Boolean::new_witness(ns!(cs, "leaf_is_left"), || {
+			Ok(leaf_hash.value()? == left.value()?)
+		})?;
+		let mut result = leaf_hash.is_eq(&leaf_is_left.select(left, right)?)?;
+
+		// every node is one of the children of the next one
+		let mut node = leaf_hash;
+		for (left, right) in &self.path {
+			let node_is_left = Boolean::new_witness(ns!(cs, "node_is_left"), || {
+				Ok(node.value()? == left.value()?)
+			})?;
+			result = result.and(&node.is_eq(&node_is_left.select(left, right)?)?)?;
+			let children = [left.to_bytes()?, right.to_bytes()?].concat();
+			node = <LedgerHashVar as FixedLengthCRHGadget<LedgerHash, Fq>>::evaluate(
+				hash_param, &children,
+			)?;
+		}
+
+		result.and(&root.is_eq(&node)?)
+	}
+}
+
+/// A void number, as a circuit variable.
+pub type VoidNumberVar = <Blake2sGadget as PRFGadget<Blake2s, Fq>>::OutputVar;
+
+/// The commitment randomness serialized in `bytes`.
+fn randomness(bytes: &[u8; 32]) -> Result<Randomness<EdwardsProjective>, SynthesisError> {
+	Fr::deserialize(bytes.as_ref())
+		.map(Randomness)
+		.map_err(|_| SynthesisError::AssignmentMissing)
+}
+
+/// The little-endian integer of `bytes`, as a field element.
+pub(crate) fn bytes_to_fp(bytes: &[UInt8<Fq>]) -> Result<FpVar<Fq>, SynthesisError> {
+	Boolean::le_bits_to_fp_var(&bytes.to_bits_le()?)
+}
+
+/// Whether `value > -value`, i.e., `value > (p - 1) / 2`.
+fn is_greater_than_half(value: &FpVar<Fq>) -> Result<Boolean<Fq>, SynthesisError> {
+	let half = <Fq as PrimeField>::Params::MODULUS_MINUS_ONE_DIV_TWO;
+
+	// compare the canonical bits of `value` with those of `half`,
+	// from the most significant one
+	let bits = value.to_bits_le()?;
+	let half_bits: Vec<bool> = BitIteratorLE::new(half).take(bits.len()).collect();
+	let mut greater = Boolean::FALSE;
+	let mut equal = Boolean::TRUE;
+	for (bit, half_bit) in bits.iter().zip(half_bits).rev() {
+		if half_bit {
+			equal = equal.and(bit)?;
+		} else {
+			greater = greater.or(&equal.and(bit)?)?;
+			equal = equal.and(&bit.not())?;
+		}
+	}
+	Ok(greater)
+}
+
+/// The compressed serialization of `point`: the little-endian bits
+/// of `x`, followed by a flag bit set iff `y > -y`.
+/// This is how `k` is fed to the commitment of `cm`, and how `cm`
+/// is stored as a leaf of the ledger.
+pub(crate) fn point_to_bytes(point: &EdwardsVar) -> Result<Vec<UInt8<Fq>>, SynthesisError> {
+	let mut bits = point.x.to_bits_le()?;
+	bits.push(is_greater_than_half(&point.y)?);
+	Ok(bits.chunks(8).map(UInt8::from_bits_le).collect())
+}
+
+/// Allocate the public input of a point serialized in `bytes`.
+pub(crate) fn point_input(
+	cs: ConstraintSystemRef<Fq>,
+	bytes: &[u8; 32],
+) -> Result<EdwardsVar, SynthesisError> {
+	EdwardsVar::new_input(cs, || {
+		manta_crypto::CommitmentOutput::deserialize(bytes.as_ref())
+			.map_err(|_| SynthesisError::AssignmentMissing)
+	})
+}
+
+/// Spend the asset of `sender`, and return its value.
+///
+/// The asset is well-formed with respect to `asset_id`, its `k` and
+/// `void_number` are the public ones, and its commitment is a leaf of the
+/// tree of the public `root`.
+/// The membership is not enforced for an asset of value zero, so that
+/// a transaction with a single real input can be padded with a dummy one.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spend(
+	cs: ConstraintSystemRef<Fq>,
+	commit_param: &CommitmentParamVar,
+	hash_param: &LedgerHashParamVar,
+	sender: &SenderMetaData,
+	asset_id: &[UInt8<Fq>],
+	k: &EdwardsVar,
+	void_number: &VoidNumberVar,
+	root: &EdwardsVar,
+) -> Result<FpVar<Fq>, SynthesisError> {
+	let asset = &sender.asset;
+
+	// pk = PRF(sk, 0)
+	let sk = UInt8::new_witness_vec(ns!(cs, "sk"), &asset.priv_info.sk)?;
+	let pk = Blake2sGadget::evaluate(&sk, &UInt8::constant_vec(&[0u8; 32]))?;
+
+	// sn = PRF(sk, rho)
+	let rho = UInt8::new_witness_vec(ns!(cs, "rho"), &asset.pub_info.rho)?;
+	Blake2sGadget::evaluate(&sk, &rho)?.enforce_equal(void_number)?;
+
+	// k = com(pk||rho, r)
+	let r = CommitmentRandomnessVar::new_witness(ns!(cs, "r"), || randomness(&asset.pub_info.r))?;
+	let payload = [pk.to_bytes()?, rho].concat();
+	CommitmentSchemeVar::commit(commit_param, &payload, &r)?.enforce_equal(k)?;
+
+	// cm = com( asset_id | v||k, s )
+	let value = UInt8::new_witness_vec(ns!(cs, "value"), &asset.priv_info.value.to_le_bytes())?;
+	let s = CommitmentRandomnessVar::new_witness(ns!(cs, "s"), || randomness(&asset.pub_info.s))?;
+	let payload = [asset_id, value.as_slice(), point_to_bytes(k)?.as_slice()].concat();
+	let cm = CommitmentSchemeVar::commit(commit_param, &payload, &s)?;
+
+	// cm is a leaf of the tree of root, unless the asset is worthless
+	let membership =
+		MembershipPathVar::new_witness(ns!(cs, "membership"), || Ok(&sender.membership))?;
+	let is_member = membership.check_membership(hash_param, root, &point_to_bytes(&cm)?)?;
+	let value = bytes_to_fp(&value)?;
+	is_member
+		.or(&value.is_zero()?)?
+		.enforce_equal(&Boolean::TRUE)?;
+
+	Ok(value)
+}
+
+/// Create the asset of `receiver`, and return its value.
+///
+/// The asset is well-formed with respect to `asset_id`, and its
+/// commitment is the public `cm`.
+pub(crate) fn receive(
+	cs: ConstraintSystemRef<Fq>,
+	commit_param: &CommitmentParamVar,
+	receiver: &MantaAssetProcessedReceiver,
+	asset_id: &[UInt8<Fq>],
+	cm: &EdwardsVar,
+) -> Result<FpVar<Fq>, SynthesisError> {
+	// cm = com( asset_id | v||k, s )
+	let value = UInt8::new_witness_vec(ns!(cs, "value"), &receiver.value.to_le_bytes())?;
+	let k = UInt8::new_witness_vec(ns!(cs, "k"), &receiver.prepared_data.k)?;
+	let s = CommitmentRandomnessVar::new_witness(ns!(cs, "s"), || {
+		randomness(&receiver.prepared_data.s)
+	})?;
+	let payload = [asset_id, value.as_slice(), k.as_slice()].concat();
+	CommitmentSchemeVar::commit(commit_param, &payload, &s)?.enforce_equal(cm)?;
+
+	bytes_to_fp(&value)
+}
+
+/// The public inputs of the inputs and outputs of a transaction.
+pub(crate) struct EntryInputs {
+	pub(crate) k: Vec<EdwardsVar>,
+	pub(crate) cm: Vec<EdwardsVar>,
+	pub(crate) void_number: Vec<VoidNumberVar>,
+	pub(crate) root: Vec<EdwardsVar>,
+}
+
+/// Allocate the public inputs of `senders` and `receivers`, in the order
+/// of the public inputs of `MultiTransferData` and `MultiReclaimData`:
+/// the `k` of every sender, the `cm` of every receiver, then the void
+/// number of every sender and the root of every sender.
+pub(crate) fn entry_inputs(
+	cs: ConstraintSystemRef<Fq>,
+	senders: &[SenderMetaData],
+	receivers: &[MantaAssetProcessedReceiver],
+) -> Result<EntryInputs, SynthesisError> {
+	let mut inputs = EntryInputs {
+		k: Vec::with_capacity(senders.len()),
+		cm: Vec::with_capacity(receivers.len()),
+		void_number: Vec::with_capacity(senders.len()),
+		root: Vec::with_capacity(senders.len()),
+	};
+	for sender in senders {
+		inputs.k.push(point_input(
+			ns!(cs, "senders.k").cs(),
+			&sender.asset.pub_info.k,
+		)?);
+	}
+	for receiver in receivers {
+		inputs
+			.cm
+			.push(point_input(ns!(cs, "receivers.cm").cs(), &receiver.utxo)?);
+	}
+	for sender in senders {
+		inputs.void_number.push(VoidNumberVar::new_input(
+			ns!(cs, "senders.void_number"),
+			|| Ok(sender.asset.void_number),
+		)?);
+	}
+	for sender in senders {
+		inputs
+			.root
+			.push(EdwardsVar::new_input(ns!(cs, "senders.root"), || {
+				Ok(sender.root)
+			})?);
+	}
+	Ok(inputs)
+}
+
+/// Spend the assets of `senders` and create those of `receivers`,
+/// against the public inputs of `inputs`, and return the total value
+/// of the senders and the total value of the receivers.
+pub(crate) fn transact(
+	cs: ConstraintSystemRef<Fq>,
+	commit_param: &CommitmentParamVar,
+	hash_param: &LedgerHashParamVar,
+	senders: &[SenderMetaData],
+	receivers: &[MantaAssetProcessedReceiver],
+	asset_id: &[UInt8<Fq>],
+	inputs: &EntryInputs,
+) -> Result<(FpVar<Fq>, FpVar<Fq>), SynthesisError> {
+	let mut spent = FpVar::zero();
+	for (i, sender) in senders.iter().enumerate() {
+		spent += spend(
+			ns!(cs, "sender").cs(),
+			commit_param,
+			hash_param,
+			sender,
+			asset_id,
+			&inputs.k[i],
+			&inputs.void_number[i],
+			&inputs.root[i],
+		)?;
+	}
+	let mut received = FpVar::zero();
+	for (i, receiver) in receivers.iter().enumerate() {
+		received += receive(
+			ns!(cs, "receiver").cs(),
+			commit_param,
+			receiver,
+			asset_id,
+			&inputs.cm[i],
+		)?;
+	}
+	Ok((spent, received))
+}
@@ -0,0 +1,52 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This crate implements the constraint systems of private transfers
+//! and reclaims, to be used with the prover of `manta-data`.
+//!
+//! Both circuits are built from the same gadgets, which mirror the
+//! native commitment, PRF and merkle tree of `manta-asset`:
+//! - `pk = PRF(sk, 0)` and `sn = PRF(sk, rho)`, with Blake2s;
+//! - `k = com(pk||rho, r)` and `cm = com( asset_id | v||k, s )`,
+//!   with the Pedersen commitment; outputs are thus committed to in the
+//!   `CommitmentVersion::V1` format, and the circuits need their own keys;
+//! - `cm` is a leaf of the ledger merkle tree of `root`.
+//!
+//! The public inputs are allocated in the order of
+//! `PRIVATE_TRANSFER_INPUT_LAYOUT` and `RECLAIM_INPUT_LAYOUT`, so that
+//! proofs are checked by the verifiers of `manta-data` as is.
+
+#![no_std]
+
+mod error;
+mod gadgets;
+mod reclaim;
+mod setup;
+mod transfer;
+
+pub use error::SetupError;
+pub use gadgets::{
+	CommitmentParamVar, CommitmentSchemeVar, LedgerHashParamVar, LedgerHashVar, MembershipPathVar,
+	VoidNumberVar,
+};
+pub use reclaim::{MultiReclaimCircuit, ReclaimCircuit};
+pub use setup::{
+	insert_verification_key, read_proving_key, read_verification_key, setup, setup_reclaim,
+	setup_transfer, write_keys, CircuitKind,
+};
+pub use transfer::{MultiTransferCircuit, TransferCircuit};
+#[cfg(test)]
+mod test;
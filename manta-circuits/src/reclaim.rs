@@ -0,0 +1,97 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the constraint system of reclaims.
+
+use crate::gadgets::*;
+use ark_ed_on_bls12_381::Fq;
+use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar, uint8::UInt8};
+use ark_relations::{
+	ns,
+	r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+use manta_data::{MultiReclaimWitness, ReclaimWitness};
+
+/// The circuit of a reclaim of `N` inputs and `M` outputs: the assets of
+/// the senders are spent, `reclaim_amount` of their total value leaves
+/// the private ledger, and the change goes to the receivers.
+/// All the assets have the public asset id.
+/// The account that receives `reclaim_amount` is a public input, so that
+/// the proof is only valid for that account.
+#[derive(Clone)]
+pub struct MultiReclaimCircuit<const N: usize, const M: usize> {
+	witness: MultiReclaimWitness<N, M>,
+}
+
+/// The circuit of a 2-in 1-out reclaim, whose public inputs are those
+/// of `RECLAIM_INPUT_LAYOUT`.
+pub type ReclaimCircuit = MultiReclaimCircuit<2, 1>;
+
+impl<const N: usize, const M: usize> From<MultiReclaimWitness<N, M>> for MultiReclaimCircuit<N, M> {
+	fn from(witness: MultiReclaimWitness<N, M>) -> Self {
+		Self { witness }
+	}
+}
+
+impl From<ReclaimWitness> for ReclaimCircuit {
+	fn from(witness: ReclaimWitness) -> Self {
+		Self {
+			witness: witness.into(),
+		}
+	}
+}
+
+impl<const N: usize, const M: usize> ConstraintSynthesizer<Fq> for MultiReclaimCircuit<N, M> {
+	fn generate_constraints(self, cs: ConstraintSystemRef<Fq>) -> Result<(), SynthesisError> {
+		let witness = self.witness;
+		let asset_id = witness
+			.senders
+			.first()
+			.map_or(0, |sender| sender.asset.asset_id);
+
+		// public inputs, in the order of the public inputs of `MultiReclaimData`
+		let inputs = entry_inputs(cs.clone(), &witness.senders, &witness.receivers)?;
+		let reclaim_amount = FpVar::new_input(ns!(cs, "reclaim_amount"), || {
+			Ok(Fq::from(witness.reclaim_amount))
+		})?;
+		// the recipient is bound by the proof, and not used otherwise
+		UInt8::new_input_vec(ns!(cs, "recipient"), &witness.recipient)?;
+		let asset_id_input = FpVar::new_input(ns!(cs, "asset_id"), || Ok(Fq::from(asset_id)))?;
+		// the network id is bound by the proof, and not used otherwise
+		FpVar::new_input(ns!(cs, "network_id"), || Ok(Fq::from(witness.network_id)))?;
+
+		let commit_param =
+			CommitmentParamVar::new_constant(ns!(cs, "commit_param"), &witness.commit_param)?;
+		let hash_param =
+			LedgerHashParamVar::new_constant(ns!(cs, "hash_param"), &witness.hash_param)?;
+		let asset_id = UInt8::new_witness_vec(ns!(cs, "asset_id"), &asset_id.to_le_bytes())?;
+		bytes_to_fp(&asset_id)?.enforce_equal(&asset_id_input)?;
+
+		let (spent, received) = transact(
+			cs,
+			&commit_param,
+			&hash_param,
+			&witness.senders,
+			&witness.receivers,
+			&asset_id,
+			&inputs,
+		)?;
+
+		// the values are below 2^64, so the sums do not wrap around
+		// for any practical arity
+		spent.enforce_equal(&(received + reclaim_amount))
+	}
+}
@@ -0,0 +1,304 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a deterministic setup of the circuits, for
+//! development networks that should not depend on the published keys.
+//!
+//! __The keys are only as secret as the seed__: anyone who knows the seed
+//! can forge proofs, so these keys must not be used on a live network.
+//!
+//! A key file is laid out as follows, with little-endian integers:
+//!
+//! | offset       | size | content                                        |
+//! |--------------|------|------------------------------------------------|
+//! | `0`          | 8    | the magic bytes `MANTAKEY`                     |
+//! | `8`          | 2    | the format version, a `u16`, currently `2`     |
+//! | `10`         | 1    | the circuit: `0` for transfer, `1` for reclaim |
+//! | `11`         | 1    | the number `N` of inputs of the circuit        |
+//! | `12`         | 1    | the number `M` of outputs of the circuit       |
+//! | `13`         | 4    | the length `n` of the verifying key, a `u32`   |
+//! | `17`         | `n`  | the uncompressed verifying key                 |
+//! | `17 + n`     | 4    | the length `m` of the proving key, a `u32`     |
+//! | `21 + n`     | `m`  | the compressed proving key                     |
+//! | `21 + n + m` | 32   | the Blake2s digest of all the bytes above      |
+//!
+//! Files of version `1` have no arity, i.e., no bytes at offsets `11`
+//! and `12`, and are read as the 2-in 2-out transfer and the 2-in 1-out
+//! reclaim circuits, the only ones that they were written for.
+//!
+//! The verifying key section is in the uncompressed encoding of the
+//! published keys, i.e., it is exactly what the verifiers take as a
+//! `VerificationKey`; `insert_verification_key` adds it to the
+//! `VerificationKeys` of its arity.
+
+use crate::{MultiReclaimCircuit, MultiTransferCircuit, SetupError};
+use ark_bls12_381::Bls12_381;
+use ark_groth16::generate_random_parameters;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::{io::Write, vec::Vec};
+use blake2::{Blake2s, Digest};
+use manta_crypto::{CommitmentParam, Groth16Vk, HashParam, VerificationKey};
+use manta_data::{
+	Arity, BatchTransferData, Groth16Pk, MultiReclaimData, MultiReclaimWitness, MultiTransferData,
+	MultiTransferWitness, VerificationKeys,
+};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+const MAGIC: &[u8; 8] = b"MANTAKEY";
+const VERSION: u16 = 2;
+/// The version of the files without an arity.
+const VERSION_1: u16 = 1;
+const CHECKSUM_SIZE: usize = 32;
+
+/// The circuits that keys are generated for, with their arity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitKind {
+	/// The transfer circuit of an arity, e.g., `MultiTransferCircuit<4, 4>`.
+	Transfer(Arity),
+	/// The reclaim circuit of an arity, e.g., `MultiReclaimCircuit<2, 1>`.
+	Reclaim(Arity),
+}
+
+impl CircuitKind {
+	/// The 2-in 2-out transfer circuit, `TransferCircuit`.
+	pub const TRANSFER: Self = CircuitKind::Transfer(MultiTransferData::<2, 2>::ARITY);
+
+	/// The 4-in 4-out transfer circuit, of `BatchTransferData`.
+	pub const BATCH_TRANSFER: Self = CircuitKind::Transfer(BatchTransferData::ARITY);
+
+	/// The 2-in 1-out reclaim circuit, `ReclaimCircuit`.
+	pub const RECLAIM: Self = CircuitKind::Reclaim(MultiReclaimData::<2, 1>::ARITY);
+
+	/// The tag of the circuit in a key file.
+	pub fn tag(self) -> u8 {
+		match self {
+			CircuitKind::Transfer(_) => 0,
+			CircuitKind::Reclaim(_) => 1,
+		}
+	}
+
+	/// The arity of the circuit.
+	pub fn arity(self) -> Arity {
+		match self {
+			CircuitKind::Transfer(arity) | CircuitKind::Reclaim(arity) => arity,
+		}
+	}
+
+	/// The circuit of a tag and an arity in a key file.
+	pub fn from_tag(tag: u8, arity: Arity) -> Result<Self, SetupError> {
+		match tag {
+			0 => Ok(CircuitKind::Transfer(arity)),
+			1 => Ok(CircuitKind::Reclaim(arity)),
+			_ => Err(SetupError::UnknownCircuit(tag)),
+		}
+	}
+}
+
+/// Generate the proving key of `circuit` from `seed`.
+/// The same seed and parameters always give the same key.
+/// The circuits of `CircuitKind::TRANSFER`, `CircuitKind::BATCH_TRANSFER`
+/// and `CircuitKind::RECLAIM` are supported; the other arities are set up
+/// with `setup_transfer` and `setup_reclaim`.
+pub fn setup(
+	circuit: CircuitKind,
+	commit_param: &CommitmentParam,
+	hash_param: &HashParam,
+	seed: [u8; 32],
+) -> Result<Groth16Pk, SetupError> {
+	match circuit {
+		CircuitKind::TRANSFER => setup_transfer::<2, 2>(commit_param, hash_param, seed),
+		CircuitKind::BATCH_TRANSFER => setup_transfer::<4, 4>(commit_param, hash_param, seed),
+		CircuitKind::RECLAIM => setup_reclaim::<2, 1>(commit_param, hash_param, seed),
+		_ => Err(SetupError::UnsupportedArity(circuit.arity())),
+	}
+}
+
+/// Generate the proving key of the transfer circuit of `N` inputs
+/// and `M` outputs from `seed`, as for `setup`.
+pub fn setup_transfer<const N: usize, const M: usize>(
+	commit_param: &CommitmentParam,
+	hash_param: &HashParam,
+	seed: [u8; 32],
+) -> Result<Groth16Pk, SetupError> {
+	let mut rng = ChaCha20Rng::from_seed(seed);
+
+	// the setup only needs the shape of the circuit,
+	// so the circuit is built from an empty witness
+	let witness = MultiTransferWitness::<N, M> {
+		commit_param: commit_param.clone(),
+		hash_param: hash_param.clone(),
+		senders: [(); N].map(|_| Default::default()),
+		receivers: [(); M].map(|_| Default::default()),
+		fee: 0,
+		network_id: 0,
+	};
+	let circuit = MultiTransferCircuit::from(witness);
+	Ok(generate_random_parameters::<Bls12_381, _, _>(
+		circuit, &mut rng,
+	)?)
+}
+
+/// Generate the proving key of the reclaim circuit of `N` inputs
+/// and `M` outputs from `seed`, as for `setup`.
+pub fn setup_reclaim<const N: usize, const M: usize>(
+	commit_param: &CommitmentParam,
+	hash_param: &HashParam,
+	seed: [u8; 32],
+) -> Result<Groth16Pk, SetupError> {
+	let mut rng = ChaCha20Rng::from_seed(seed);
+
+	// the setup only needs the shape of the circuit,
+	// so the circuit is built from an empty witness
+	let witness = MultiReclaimWitness::<N, M> {
+		commit_param: commit_param.clone(),
+		hash_param: hash_param.clone(),
+		senders: [(); N].map(|_| Default::default()),
+		receivers: [(); M].map(|_| Default::default()),
+		reclaim_amount: 0,
+		recipient: [0u8; 32],
+		network_id: 0,
+	};
+	let circuit = MultiReclaimCircuit::from(witness);
+	Ok(generate_random_parameters::<Bls12_381, _, _>(
+		circuit, &mut rng,
+	)?)
+}
+
+/// Write the key file of `proving_key`, generated for `circuit`.
+pub fn write_keys<W: Write>(
+	circuit: CircuitKind,
+	proving_key: &Groth16Pk,
+	mut writer: W,
+) -> Result<(), SetupError> {
+	let mut buf = Vec::new();
+	buf.extend_from_slice(MAGIC);
+	buf.extend_from_slice(&VERSION.to_le_bytes());
+	buf.push(circuit.tag());
+	let arity = circuit.arity();
+	for n in [arity.inputs, arity.outputs].iter() {
+		if *n > u8::MAX as usize {
+			return Err(SetupError::UnsupportedArity(arity));
+		}
+		buf.push(*n as u8);
+	}
+	let mut verifying_key = Vec::new();
+	proving_key.vk.serialize_uncompressed(&mut verifying_key)?;
+	write_section(&mut buf, &verifying_key);
+	let mut key = Vec::new();
+	proving_key.serialize(&mut key)?;
+	write_section(&mut buf, &key);
+	let checksum = Blake2s::digest(&buf);
+
+	writer
+		.write_all(&buf)
+		.and_then(|_| writer.write_all(&checksum))
+		.map_err(SerializationError::from)?;
+	Ok(())
+}
+
+/// Read the proving key of a key file, and the circuit it is for.
+pub fn read_proving_key(bytes: &[u8]) -> Result<(CircuitKind, Groth16Pk), SetupError> {
+	let (circuit, _, proving_key) = parse(bytes)?;
+	Ok((circuit, Groth16Pk::deserialize(proving_key)?))
+}
+
+/// Read the verifying key of a key file, and the circuit it is for.
+pub fn read_verification_key(bytes: &[u8]) -> Result<(CircuitKind, Groth16Vk), SetupError> {
+	let (circuit, verifying_key, _) = parse(bytes)?;
+	Ok((circuit, Groth16Vk::deserialize_uncompressed(verifying_key)?))
+}
+
+/// Add the verifying key of a key file to `keys`, for the circuit and the
+/// arity of its header; `VerificationKeys` rejects a key that does not
+/// have the public inputs of that arity. The verifying key section is
+/// used as is, and so is borrowed from `bytes` for as long as `keys`.
+pub fn insert_verification_key(
+	keys: &mut VerificationKeys,
+	bytes: &'static [u8],
+) -> Result<CircuitKind, SetupError> {
+	let (circuit, verifying_key, _) = parse(bytes)?;
+	let key = VerificationKey {
+		data: verifying_key,
+	};
+	match circuit {
+		CircuitKind::Transfer(arity) => keys.insert_transfer(arity, key),
+		CircuitKind::Reclaim(arity) => keys.insert_reclaim(arity, key),
+	}
+	.map_err(SetupError::Verification)?;
+	Ok(circuit)
+}
+
+/// Append the length of `section`, and `section`, to `buf`.
+fn write_section(buf: &mut Vec<u8>, section: &[u8]) {
+	buf.extend_from_slice(&(section.len() as u32).to_le_bytes());
+	buf.extend_from_slice(section);
+}
+
+/// Split `bytes` into the first section and the rest.
+fn read_section(bytes: &[u8]) -> Result<(&[u8], &[u8]), SetupError> {
+	if bytes.len() < 4 {
+		return Err(SetupError::LengthMismatch);
+	}
+	let (len, rest) = bytes.split_at(4);
+	let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+	if rest.len() < len {
+		return Err(SetupError::LengthMismatch);
+	}
+	Ok(rest.split_at(len))
+}
+
+/// Check the header and the checksum of a key file, and return
+/// its circuit, its verifying key section and its proving key section.
+fn parse(bytes: &[u8]) -> Result<(CircuitKind, &[u8], &[u8]), SetupError> {
+	if bytes.len() < MAGIC.len() + 3 + CHECKSUM_SIZE {
+		return Err(SetupError::LengthMismatch);
+	}
+	let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+	if &content[..MAGIC.len()] != MAGIC {
+		return Err(SetupError::BadMagic);
+	}
+	let version = u16::from_le_bytes([content[8], content[9]]);
+	if version != VERSION && version != VERSION_1 {
+		return Err(SetupError::UnsupportedVersion(version));
+	}
+	if Blake2s::digest(content).as_slice() != checksum {
+		return Err(SetupError::ChecksumMismatch);
+	}
+	let tag = content[10];
+	let (circuit, sections) = if version == VERSION_1 {
+		let circuit = match CircuitKind::from_tag(tag, MultiTransferData::<2, 2>::ARITY)? {
+			CircuitKind::Transfer(_) => CircuitKind::TRANSFER,
+			CircuitKind::Reclaim(_) => CircuitKind::RECLAIM,
+		};
+		(circuit, &content[11..])
+	} else {
+		if content.len() < 13 {
+			return Err(SetupError::LengthMismatch);
+		}
+		let arity = Arity {
+			inputs: content[11] as usize,
+			outputs: content[12] as usize,
+		};
+		(CircuitKind::from_tag(tag, arity)?, &content[13..])
+	};
+
+	let (verifying_key, rest) = read_section(sections)?;
+	let (proving_key, rest) = read_section(rest)?;
+	if !rest.is_empty() {
+		return Err(SetupError::LengthMismatch);
+	}
+	Ok((circuit, verifying_key, proving_key))
+}
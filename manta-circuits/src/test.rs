@@ -0,0 +1,353 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::Fq;
+use ark_groth16::generate_random_parameters;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_serialize::CanonicalSerialize;
+use ark_std::{
+	boxed::Box,
+	rand::{CryptoRng, RngCore},
+	vec::Vec,
+};
+use blake2::{Blake2s, Digest};
+use manta_asset::{
+	CommitmentVersion, MantaAsset, MantaAssetFullReceiver, MantaAssetProcessedReceiver, Sampling,
+	TEST_ASSET, TEST_NETWORK,
+};
+use manta_crypto::{
+	CommitmentParam, Groth16Vk, HashParam, MantaSerDes, MantaZKPVerifier, VerificationKey,
+	COMMIT_PARAM, HASH_PARAM,
+};
+use manta_data::{
+	prove_multi_transfer, prove_reclaim, prove_transfer, BatchTransferData, BuildMetadata,
+	MultiReclaimData, MultiReclaimWitness, MultiTransferData, MultiTransferWitness,
+	PreparedVerifier, ReclaimWitness, SenderMetaData, TransferWitness, VerificationError,
+	VerificationKeys,
+};
+use manta_ledger::{shard_index_of, LedgerSharding, MantaPrivateAssetLedger};
+
+// the assets of `values`, posted to a fresh ledger
+fn senders<R: RngCore + CryptoRng>(
+	commit_param: &CommitmentParam,
+	hash_param: &HashParam,
+	values: [u64; 2],
+	rng: &mut R,
+) -> (SenderMetaData, SenderMetaData) {
+	let mut ledger = MantaPrivateAssetLedger::default();
+	let assets: Vec<MantaAsset> = values
+		.iter()
+		.map(|value| {
+			let mut sk = [0u8; 32];
+			rng.fill_bytes(&mut sk);
+			let asset = MantaAsset::sample(commit_param, &sk, &TEST_ASSET, value, rng).unwrap();
+			ledger.update(&asset.utxo, hash_param.clone()).unwrap();
+			asset
+		})
+		.collect();
+
+	let metadata = |asset: &MantaAsset| {
+		let shard = shard_index_of(&asset.utxo) as u8;
+		asset
+			.build(hash_param, &ledger.current_tree(shard).to_vec())
+			.unwrap()
+	};
+	(metadata(&assets[0]), metadata(&assets[1]))
+}
+
+// a receiver of `value`
+fn receiver<R: RngCore + CryptoRng>(
+	commit_param: &CommitmentParam,
+	value: u64,
+	rng: &mut R,
+) -> MantaAssetProcessedReceiver {
+	let mut sk = [0u8; 32];
+	rng.fill_bytes(&mut sk);
+	MantaAssetFullReceiver::sample_for_network(commit_param, &sk, &TEST_ASSET, &TEST_NETWORK, rng)
+		.unwrap()
+		.shielded_address
+		.process_with_version(&value, CommitmentVersion::V1, rng)
+		.unwrap()
+}
+
+// the verification key, in the uncompressed form that the verifiers take
+fn verification_key(vk: &Groth16Vk) -> VerificationKey {
+	let mut data = Vec::new();
+	vk.serialize_uncompressed(&mut data).unwrap();
+	VerificationKey {
+		data: Box::leak(data.into_boxed_slice()),
+	}
+}
+
+fn transfer_witness<R: RngCore + CryptoRng>(values: [u64; 4], rng: &mut R) -> TransferWitness {
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let (sender_1, sender_2) = senders(&commit_param, &hash_param, [values[0], values[1]], rng);
+
+	TransferWitness {
+		receiver_1: receiver(&commit_param, values[2], rng),
+		receiver_2: receiver(&commit_param, values[3], rng),
+		commit_param,
+		hash_param,
+		sender_1,
+		sender_2,
+		fee: 0,
+		network_id: TEST_NETWORK,
+	}
+}
+
+#[test]
+fn test_transfer_circuit() {
+	let mut rng = ark_std::test_rng();
+	let witness = transfer_witness([10, 20, 5, 25], &mut rng);
+	let pk = generate_random_parameters::<Bls12_381, _, _>(
+		TransferCircuit::from(witness.clone()),
+		&mut rng,
+	)
+	.unwrap();
+
+	let data = prove_transfer::<TransferCircuit, _>(&pk, witness, &mut rng).unwrap();
+	assert!(data.verify(&verification_key(&pk.vk)));
+	let verifier = PreparedVerifier::from_vk(pk.vk.clone());
+	assert_eq!(verifier.verify_transfer(&data), Ok(()));
+
+	// a proof does not carry over to other public inputs
+	let mut forged = data.clone();
+	forged.receiver_1.cm = data.receiver_2.cm;
+	assert!(verifier.verify_transfer(&forged).is_err());
+
+	// nor to another network
+	let mut forged = data.clone();
+	forged.network_id += 1;
+	assert!(verifier.verify_transfer(&forged).is_err());
+}
+
+#[test]
+fn test_reclaim_circuit() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let (sender_1, sender_2) = senders(&commit_param, &hash_param, [10, 20], &mut rng);
+	let witness = ReclaimWitness {
+		receiver: receiver(&commit_param, 12, &mut rng),
+		reclaim_amount: 18,
+		recipient: [7u8; 32],
+		network_id: TEST_NETWORK,
+		commit_param,
+		hash_param,
+		sender_1,
+		sender_2,
+	};
+	let pk = generate_random_parameters::<Bls12_381, _, _>(
+		ReclaimCircuit::from(witness.clone()),
+		&mut rng,
+	)
+	.unwrap();
+
+	let data = prove_reclaim::<ReclaimCircuit, _>(&pk, witness, &mut rng).unwrap();
+	assert!(data.verify(&verification_key(&pk.vk)));
+	let verifier = PreparedVerifier::from_vk(pk.vk.clone());
+	assert_eq!(verifier.verify_reclaim(&data), Ok(()));
+
+	let mut forged = data.clone();
+	forged.reclaim_amount += 1;
+	assert!(verifier.verify_reclaim(&forged).is_err());
+
+	// the reclaim cannot be resubmitted for another account
+	let mut forged = data.clone();
+	forged.recipient = [8u8; 32];
+	assert!(verifier.verify_reclaim(&forged).is_err());
+}
+
+#[test]
+fn test_unbalanced_transfer_is_unsatisfied() {
+	let mut rng = ark_std::test_rng();
+	let balanced = transfer_witness([10, 20, 5, 25], &mut rng);
+	let cs = ConstraintSystem::<Fq>::new_ref();
+	TransferCircuit::from(balanced)
+		.generate_constraints(cs.clone())
+		.unwrap();
+	assert!(cs.is_satisfied().unwrap());
+
+	let unbalanced = transfer_witness([10, 20, 5, 26], &mut rng);
+	let cs = ConstraintSystem::<Fq>::new_ref();
+	TransferCircuit::from(unbalanced)
+		.generate_constraints(cs.clone())
+		.unwrap();
+	assert!(!cs.is_satisfied().unwrap());
+}
+
+#[test]
+fn test_seeded_setup() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let seed = [7u8; 32];
+	let pk = setup(CircuitKind::TRANSFER, &commit_param, &hash_param, seed).unwrap();
+	let again = setup(CircuitKind::TRANSFER, &commit_param, &hash_param, seed).unwrap();
+	assert_eq!(pk.vk, again.vk);
+
+	let mut file = Vec::new();
+	write_keys(CircuitKind::TRANSFER, &pk, &mut file).unwrap();
+	assert_eq!(file[10..13], [0, 2, 2]);
+	let (circuit, loaded_pk) = read_proving_key(&file).unwrap();
+	assert_eq!(circuit, CircuitKind::TRANSFER);
+	let (circuit, vk) = read_verification_key(&file).unwrap();
+	assert_eq!(circuit, CircuitKind::TRANSFER);
+	assert_eq!(vk, pk.vk);
+
+	// the keys of the file work together with the verifiers, and the
+	// verifying key section is a `VerificationKey` as is
+	let witness = transfer_witness([10, 20, 5, 25], &mut rng);
+	let data = prove_transfer::<TransferCircuit, _>(&loaded_pk, witness, &mut rng).unwrap();
+	assert_eq!(PreparedVerifier::from_vk(vk).verify_transfer(&data), Ok(()));
+	let mut section = Vec::new();
+	pk.vk.serialize_uncompressed(&mut section).unwrap();
+	assert_eq!(file[13..17], (section.len() as u32).to_le_bytes());
+	assert_eq!(file[17..17 + section.len()], section[..]);
+	assert!(data.verify(&verification_key(&pk.vk)));
+	let mut keys = VerificationKeys::default();
+	let bytes: &'static [u8] = Box::leak(file.clone().into_boxed_slice());
+	assert_eq!(
+		insert_verification_key(&mut keys, bytes).unwrap(),
+		CircuitKind::TRANSFER
+	);
+	assert_eq!(
+		MultiTransferData::<2, 2>::from(data).verify_with(&keys),
+		Ok(())
+	);
+
+	// a file of version 1, without an arity, is for the 2-in circuits
+	let mut legacy = file[..file.len() - 32].to_vec();
+	legacy[8] = 1;
+	legacy.drain(11..13);
+	let checksum = Blake2s::digest(&legacy);
+	legacy.extend_from_slice(&checksum);
+	let (circuit, vk) = read_verification_key(&legacy).unwrap();
+	assert_eq!(circuit, CircuitKind::TRANSFER);
+	assert_eq!(vk, pk.vk);
+
+	let mut tampered = file.to_vec();
+	tampered[20] ^= 1;
+	assert!(matches!(
+		read_proving_key(&tampered),
+		Err(SetupError::ChecksumMismatch)
+	));
+}
+
+#[test]
+fn test_multi_transfer_circuit() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let (sender_1, sender_2) = senders(&commit_param, &hash_param, [10, 20], &mut rng);
+	let (sender_3, sender_4) = senders(&commit_param, &hash_param, [5, 0], &mut rng);
+	let witness = MultiTransferWitness {
+		receivers: [
+			receiver(&commit_param, 1, &mut rng),
+			receiver(&commit_param, 2, &mut rng),
+			receiver(&commit_param, 3, &mut rng),
+			receiver(&commit_param, 28, &mut rng),
+		],
+		senders: [sender_1, sender_2, sender_3, sender_4],
+		commit_param,
+		hash_param,
+		fee: 1,
+		network_id: TEST_NETWORK,
+	};
+	let pk = generate_random_parameters::<Bls12_381, _, _>(
+		MultiTransferCircuit::from(witness.clone()),
+		&mut rng,
+	)
+	.unwrap();
+
+	// a 4-in 4-out transfer is verified with the key of its arity
+	let data: BatchTransferData =
+		prove_multi_transfer::<MultiTransferCircuit<4, 4>, _, 4, 4>(&pk, witness, &mut rng)
+			.unwrap();
+	let mut keys = VerificationKeys::default();
+	keys.insert_transfer(BatchTransferData::ARITY, verification_key(&pk.vk))
+		.unwrap();
+	assert_eq!(data.verify_with(&keys), Ok(()));
+
+	// the arity of the key is recorded in its file, and checked against
+	// the public inputs of the key
+	let mut file = Vec::new();
+	write_keys(CircuitKind::BATCH_TRANSFER, &pk, &mut file).unwrap();
+	assert_eq!(file[10..13], [0, 4, 4]);
+	let bytes: &'static [u8] = Box::leak(file.into_boxed_slice());
+	let mut keys = VerificationKeys::default();
+	assert_eq!(
+		insert_verification_key(&mut keys, bytes).unwrap(),
+		CircuitKind::BATCH_TRANSFER
+	);
+	assert_eq!(data.verify_with(&keys), Ok(()));
+	let mut file = Vec::new();
+	write_keys(CircuitKind::TRANSFER, &pk, &mut file).unwrap();
+	let bytes: &'static [u8] = Box::leak(file.into_boxed_slice());
+	assert!(matches!(
+		insert_verification_key(&mut keys, bytes),
+		Err(SetupError::Verification(
+			VerificationError::KeyMismatch { .. }
+		))
+	));
+
+	let mut forged = data.clone();
+	forged.fee += 1;
+	assert_eq!(
+		forged.verify_with(&keys),
+		Err(VerificationError::ProofRejected)
+	);
+}
+
+#[test]
+fn test_multi_reclaim_circuit() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let (sender_1, sender_2) = senders(&commit_param, &hash_param, [10, 20], &mut rng);
+	let (sender_3, _) = senders(&commit_param, &hash_param, [5, 0], &mut rng);
+	let witness = MultiReclaimWitness {
+		receivers: [receiver(&commit_param, 12, &mut rng)],
+		senders: [sender_1, sender_2, sender_3],
+		commit_param,
+		hash_param,
+		reclaim_amount: 23,
+		recipient: [7u8; 32],
+		network_id: TEST_NETWORK,
+	};
+
+	// a 3-in 1-out reclaim has the public inputs of its payload
+	let cs = ConstraintSystem::<Fq>::new_ref();
+	MultiReclaimCircuit::from(witness.clone())
+		.generate_constraints(cs.clone())
+		.unwrap();
+	assert!(cs.is_satisfied().unwrap());
+	assert_eq!(
+		cs.num_instance_variables(),
+		1 + MultiReclaimData::<3, 1>::PUBLIC_INPUT_LEN
+	);
+
+	let mut unbalanced = witness;
+	unbalanced.reclaim_amount += 1;
+	let cs = ConstraintSystem::<Fq>::new_ref();
+	MultiReclaimCircuit::from(unbalanced)
+		.generate_constraints(cs.clone())
+		.unwrap();
+	assert!(!cs.is_satisfied().unwrap());
+}
@@ -0,0 +1,183 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a builder of private transfers.
+//! It is only available with the `prover` feature.
+//!
+//! The builder pays an amount to a recipient out of a pool of owned
+//! assets: it selects one or two inputs that cover the amount, sends
+//! the rest back to the sender as a change output, and pads a single
+//! input with a dummy one (see `SenderMetaData::dummy`), to fit the
+//! 2-in 2-out shape of the transfer circuit. The inputs also cover the
+//! fee of the transfer, if any.
+//!
+//! The transfer is for the network of the shielded addresses, so that an
+//! address of a testnet cannot be paid on a mainnet.
+//!
+//! The outputs are committed to in the `CommitmentVersion::V1` format,
+//! which the relations that the witness is checked against expect.
+
+use super::*;
+use ark_ed_on_bls12_381::Fq;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_std::{
+	rand::{CryptoRng, RngCore},
+	vec::Vec,
+};
+use manta_asset::{CommitmentVersion, MantaAssetShieldedAddress};
+use manta_crypto::{CommitmentParam, HashParam};
+
+/// The format of the commitments of the outputs.
+const VERSION: CommitmentVersion = CommitmentVersion::V1;
+
+/// A builder of a private transfer of `amount` to `recipient`.
+pub struct TransferBuilder {
+	commit_param: CommitmentParam,
+	hash_param: HashParam,
+	recipient: MantaAssetShieldedAddress,
+	amount: u64,
+	change: MantaAssetShieldedAddress,
+	fee: u64,
+}
+
+impl TransferBuilder {
+	/// A transfer of `amount` to `recipient`, whose change goes to `change`.
+	/// `change` is a shielded address of the sender, who keeps the
+	/// matching `MantaAssetFullReceiver` to spend the change later.
+	pub fn new(
+		commit_param: CommitmentParam,
+		hash_param: HashParam,
+		recipient: MantaAssetShieldedAddress,
+		amount: u64,
+		change: MantaAssetShieldedAddress,
+	) -> Self {
+		Self {
+			commit_param,
+			hash_param,
+			recipient,
+			amount,
+			change,
+			fee: 0,
+		}
+	}
+
+	/// Pay a public `fee` out of the inputs, in the asset of the transfer.
+	pub fn with_fee(mut self, fee: u64) -> Self {
+		self.fee = fee;
+		self
+	}
+
+	/// Build the witness of the transfer, with inputs selected from `pool`.
+	/// The witness is checked against the relations of the circuit.
+	pub fn build_witness<R: RngCore + CryptoRng>(
+		&self,
+		pool: &[SenderMetaData],
+		rng: &mut R,
+	) -> Result<TransferWitness, BuildError> {
+		let asset_id = self.recipient.asset_id;
+		if self.change.asset_id != asset_id {
+			return Err(BuildError::MixedAssetIds);
+		}
+		let network_id = self.recipient.network_id;
+		if self.change.network_id != network_id {
+			return Err(BuildError::MixedNetworks);
+		}
+
+		let cost = self
+			.amount
+			.checked_add(self.fee)
+			.ok_or(BuildError::ValueOverflow)?;
+		let (sender_1, sender_2) = match select_inputs(pool, asset_id, cost as u128) {
+			Some((first, Some(second))) => (pool[first].clone(), pool[second].clone()),
+			Some((first, None)) => {
+				let dummy =
+					SenderMetaData::dummy(&self.commit_param, asset_id, pool[first].root, rng)?;
+				(pool[first].clone(), dummy)
+			}
+			None => return Err(BuildError::InsufficientFunds { total: cost }),
+		};
+		let change = sender_1.asset.priv_info.value as u128
+			+ sender_2.asset.priv_info.value as u128
+			- cost as u128;
+
+		let witness = TransferWitness {
+			receiver_1: self
+				.recipient
+				.process_with_version(&self.amount, VERSION, rng)?,
+			receiver_2: self
+				.change
+				.process_with_version(&(change as u64), VERSION, rng)?,
+			commit_param: self.commit_param.clone(),
+			hash_param: self.hash_param.clone(),
+			sender_1,
+			sender_2,
+			fee: self.fee,
+			network_id,
+		};
+		witness.check_relations().map_err(BuildError::Relations)?;
+		Ok(witness)
+	}
+
+	/// Build the transfer, with inputs selected from `pool`,
+	/// and prove it with the circuit `C`.
+	pub fn prove<C, R>(
+		&self,
+		pool: &[SenderMetaData],
+		proving_key: &Groth16Pk,
+		rng: &mut R,
+	) -> Result<PrivateTransferData, BuildError>
+	where
+		C: ConstraintSynthesizer<Fq> + From<TransferWitness>,
+		R: RngCore + CryptoRng,
+	{
+		let witness = self.build_witness(pool, rng)?;
+		Ok(prove_transfer::<C, R>(proving_key, witness, rng)?)
+	}
+}
+
+/// The positions of the inputs in `pool` that pay `amount` of `asset_id`:
+/// the smallest single asset that covers it if any, and otherwise the
+/// pair of assets of the smallest total that covers it.
+/// The change of the selected inputs always fits in a `u64`.
+fn select_inputs(
+	pool: &[SenderMetaData],
+	asset_id: AssetId,
+	amount: u128,
+) -> Option<(usize, Option<usize>)> {
+	let value = |i: usize| pool[i].asset.priv_info.value as u128;
+	let candidates: Vec<usize> = (0..pool.len())
+		.filter(|&i| pool[i].asset.asset_id == asset_id && value(i) > 0)
+		.collect();
+
+	let single = candidates
+		.iter()
+		.filter(|&&i| value(i) >= amount)
+		.min_by_key(|&&i| value(i));
+	if let Some(&i) = single {
+		return Some((i, None));
+	}
+
+	candidates
+		.iter()
+		.enumerate()
+		.flat_map(|(n, &i)| candidates[n + 1..].iter().map(move |&j| (i, j)))
+		.filter(|&(i, j)| {
+			let total = value(i) + value(j);
+			total >= amount && total - amount <= u64::MAX as u128
+		})
+		.min_by_key(|&(i, j)| value(i) + value(j))
+		.map(|(i, j)| (i, Some(j)))
+}
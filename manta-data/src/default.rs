@@ -19,6 +19,9 @@ use super::*;
 impl Default for PrivateTransferData {
 	fn default() -> Self {
 		Self {
+			network_id: NetworkId::default(),
+			asset_id: AssetId::default(),
+			fee: 0,
 			sender_1: SenderData::default(),
 			sender_2: SenderData::default(),
 			receiver_1: ReceiverData::default(),
@@ -28,11 +31,13 @@ impl Default for PrivateTransferData {
 	}
 }
 
-impl Default for ReclaimData {
+impl<AccountId: ReclaimAccount> Default for ReclaimData<AccountId> {
 	fn default() -> Self {
 		Self {
+			network_id: NetworkId::default(),
 			asset_id: AssetId::default(),
 			reclaim_amount: 0,
+			recipient: AccountId::decode_recipient([0u8; 32]),
 			sender_1: SenderData::default(),
 			sender_2: SenderData::default(),
 			receiver: ReceiverData::default(),
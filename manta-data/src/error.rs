@@ -0,0 +1,153 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module defines the errors raised by the verification of proofs,
+//! by the decoding of transactions, by the application of transactions to
+//! the ledger, and by the construction of transactions.
+
+#[cfg(feature = "prover")]
+use crate::Relation;
+#[cfg(feature = "prover")]
+use ark_std::vec::Vec;
+use manta_asset::NetworkId;
+use manta_error::MantaError;
+use manta_ledger::LedgerError;
+
+/// The step of the verification of a proof that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationError {
+	/// The verification key cannot be deserialized.
+	InvalidVerificationKey,
+	/// The proof cannot be deserialized.
+	InvalidProof,
+	/// A field of the payload cannot be turned into public inputs.
+	/// The field is named as in the payload, e.g., `sender_2.root`.
+	MalformedField(&'static str),
+	/// A field of an input or an output of a transaction of any arity
+	/// cannot be turned into public inputs. The field is named as in
+	/// the payload, e.g., `senders.root`, and `index` is the position
+	/// of the input or the output.
+	MalformedEntry { field: &'static str, index: usize },
+	/// A field of the payload is not a public input of the circuit of its
+	/// version, and is not the default, e.g., the `fee` of a `V0` private
+	/// transfer; the proof cannot bind it.
+	UnboundField(&'static str),
+	/// No verification key is known for the arity of the transaction.
+	UnsupportedArity { inputs: usize, outputs: usize },
+	/// The verification key is for a circuit of `found` public inputs,
+	/// while the transaction has `expected`, e.g., the key is for
+	/// another arity.
+	KeyMismatch { expected: usize, found: usize },
+	/// The pairing check cannot be carried out.
+	PairingError,
+	/// The pairing check fails: the proof is not valid for the payload.
+	ProofRejected,
+	/// The transaction is for the network `found`, rather than for the
+	/// network `expected` that verifies it.
+	WrongNetwork {
+		expected: NetworkId,
+		found: NetworkId,
+	},
+	/// The commitment of a mint does not open to its amount.
+	InvalidMint,
+}
+
+/// An error that is raised when decoding a `MantaTransaction`.
+#[derive(Debug)]
+pub enum DecodeError {
+	/// The transaction is encoded in a version that is not supported.
+	UnsupportedVersion(u8),
+	/// The tag does not name a kind of transaction.
+	UnknownTag(u8),
+	/// The header or the payload of the transaction is malformed.
+	Malformed(MantaError),
+}
+
+impl From<MantaError> for DecodeError {
+	fn from(e: MantaError) -> Self {
+		DecodeError::Malformed(e)
+	}
+}
+
+/// The error of `MantaSerDes::deserialize`: an unsupported version or an
+/// unknown tag is a failed sanity check.
+impl From<DecodeError> for MantaError {
+	fn from(e: DecodeError) -> Self {
+		match e {
+			DecodeError::Malformed(e) => e,
+			_ => MantaError::SanityCheckFail,
+		}
+	}
+}
+
+/// An error that is raised when applying a transaction to the ledger.
+#[derive(Debug)]
+pub enum TransitionError {
+	/// The transaction is for the network `found`, rather than for the
+	/// network `expected` of the ledger.
+	WrongNetwork {
+		expected: NetworkId,
+		found: NetworkId,
+	},
+	/// The ledger rejects the transaction.
+	Ledger(LedgerError),
+}
+
+impl From<LedgerError> for TransitionError {
+	fn from(e: LedgerError) -> Self {
+		TransitionError::Ledger(e)
+	}
+}
+
+/// An error that is raised when building a transaction.
+#[cfg(feature = "prover")]
+#[derive(Debug)]
+pub enum BuildError {
+	/// The assets of the pool with the asset id of the transaction
+	/// cannot cover the `total` of the amount and the fee with at most
+	/// two inputs.
+	InsufficientFunds { total: u64 },
+	/// The amount and the fee add up to more than `u64::MAX`.
+	ValueOverflow,
+	/// The recipient and the change addresses are for different asset ids.
+	MixedAssetIds,
+	/// The recipient and the change addresses are for different networks.
+	MixedNetworks,
+	/// The witness of the transaction violates some relations of the circuit.
+	Relations(Vec<Relation>),
+	/// An error from the underlying cryptographic primitives.
+	Crypto(MantaError),
+}
+
+#[cfg(feature = "prover")]
+impl From<MantaError> for BuildError {
+	fn from(e: MantaError) -> Self {
+		BuildError::Crypto(e)
+	}
+}
+
+/// An error that is raised when planning a payment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanError {
+	/// The notes with the asset id of the payment do not cover the
+	/// `total` of the amount and the fee.
+	InsufficientFunds { available: u128, total: u64 },
+	/// The amount and the fee, or a merged note, add up to more than
+	/// `u64::MAX`.
+	ValueOverflow,
+	/// A fee is requested for a reclaim, which carries no fee.
+	ReclaimFee,
+}
@@ -0,0 +1,146 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the identifiers of transactions, for mempools,
+//! explorers and receipts.
+//!
+//! Both identifiers are Blake2s digests of a domain separator, the tag of
+//! the kind of the transaction (as in `MantaTransaction`) and its payload:
+//! - the transaction hash covers the whole payload;
+//! - the transaction id covers the payload without the proof, and without
+//!   the `k`, `sender_pk` and `cipher` of the receivers, which are zeroed.
+//!
+//! A Groth16 proof is malleable: anyone can turn it into another valid
+//! proof of the same statement. Nor does the proof constrain the fields
+//! of the receivers other than `cm`, so a relayer can rewrite them without
+//! invalidating the transaction. The transaction hash of a transaction
+//! changes along, while its id does not; the id is the one to track a
+//! transaction by, and the hash tells apart the exact bytes that were
+//! submitted.
+
+use super::*;
+use crate::transaction::{MINT_TAG, PRIVATE_TRANSFER_TAG, RECLAIM_TAG};
+use ark_std::vec::Vec;
+use blake2::{Blake2s, Digest};
+use manta_crypto::MantaSerDes;
+use manta_error::MantaError;
+
+/// The domain separator of transaction hashes.
+const HASH_DOMAIN: &[u8] = b"manta-transaction-hash";
+/// The domain separator of transaction ids.
+const ID_DOMAIN: &[u8] = b"manta-transaction-id";
+/// The size of the proof at the end of a transfer or a reclaim payload.
+const PROOF_SIZE: usize = 192;
+
+/// The canonical identifiers of a transaction.
+pub trait TransactionId {
+	/// The hash of the canonical serialization of the transaction.
+	fn transaction_hash(&self) -> Result<[u8; 32], MantaError>;
+
+	/// The hash of the canonical serialization of the transaction,
+	/// without the fields that its proof does not constrain; it is not
+	/// affected by a relayer that malleates the proof or rewrites them.
+	fn transaction_id(&self) -> Result<[u8; 32], MantaError>;
+}
+
+impl TransactionId for MintData {
+	fn transaction_hash(&self) -> Result<[u8; 32], MantaError> {
+		digest(HASH_DOMAIN, MINT_TAG, self, 0)
+	}
+
+	/// A mint has no proof, so its id is over its whole payload.
+	fn transaction_id(&self) -> Result<[u8; 32], MantaError> {
+		digest(ID_DOMAIN, MINT_TAG, self, 0)
+	}
+}
+
+impl TransactionId for PrivateTransferData {
+	fn transaction_hash(&self) -> Result<[u8; 32], MantaError> {
+		digest(HASH_DOMAIN, PRIVATE_TRANSFER_TAG, self, 0)
+	}
+
+	fn transaction_id(&self) -> Result<[u8; 32], MantaError> {
+		let bound = PrivateTransferData {
+			receiver_1: bound_receiver(&self.receiver_1),
+			receiver_2: bound_receiver(&self.receiver_2),
+			..self.clone()
+		};
+		digest(ID_DOMAIN, PRIVATE_TRANSFER_TAG, &bound, PROOF_SIZE)
+	}
+}
+
+impl<AccountId: ReclaimAccount> TransactionId for ReclaimData<AccountId> {
+	fn transaction_hash(&self) -> Result<[u8; 32], MantaError> {
+		digest(HASH_DOMAIN, RECLAIM_TAG, self, 0)
+	}
+
+	fn transaction_id(&self) -> Result<[u8; 32], MantaError> {
+		let bound = ReclaimData {
+			receiver: bound_receiver(&self.receiver),
+			..self.clone()
+		};
+		digest(ID_DOMAIN, RECLAIM_TAG, &bound, PROOF_SIZE)
+	}
+}
+
+impl TransactionId for MantaTransaction {
+	fn transaction_hash(&self) -> Result<[u8; 32], MantaError> {
+		match self {
+			MantaTransaction::Mint(data) => data.transaction_hash(),
+			MantaTransaction::PrivateTransfer(data) => data.transaction_hash(),
+			MantaTransaction::Reclaim(data) => data.transaction_hash(),
+		}
+	}
+
+	fn transaction_id(&self) -> Result<[u8; 32], MantaError> {
+		match self {
+			MantaTransaction::Mint(data) => data.transaction_id(),
+			MantaTransaction::PrivateTransfer(data) => data.transaction_id(),
+			MantaTransaction::Reclaim(data) => data.transaction_id(),
+		}
+	}
+}
+
+/// The fields of `receiver` that the proof constrains, i.e., its `cm`;
+/// the other fields are zeroed.
+fn bound_receiver(receiver: &ReceiverData) -> ReceiverData {
+	ReceiverData {
+		cm: receiver.cm,
+		..Default::default()
+	}
+}
+
+/// The Blake2s digest of `domain`, `tag` and the payload of `data`,
+/// without the last `skip` bytes of the payload.
+fn digest<T: MantaSerDes>(
+	domain: &[u8],
+	tag: u8,
+	data: &T,
+	skip: usize,
+) -> Result<[u8; 32], MantaError> {
+	let mut payload = Vec::new();
+	data.serialize(&mut payload)?;
+	payload.truncate(payload.len() - skip);
+
+	let mut hasher = Blake2s::new();
+	hasher.update(domain);
+	hasher.update(&[tag]);
+	hasher.update(&payload);
+
+	let mut hash = [0u8; 32];
+	hash.copy_from_slice(&hasher.finalize());
+	Ok(hash)
+}
@@ -0,0 +1,374 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module builds the public inputs of the private transfer and
+//! reclaim circuits. The order and the encoding of the inputs are also
+//! described by `PRIVATE_TRANSFER_INPUT_LAYOUT` and `RECLAIM_INPUT_LAYOUT`,
+//! so that a prover or an external verifier can build the exact same vector.
+//! The published keys have the layouts of `PayloadVersion::V0`, e.g.,
+//! `PRIVATE_TRANSFER_INPUT_LAYOUT_V0`, and `public_inputs_of` builds the
+//! public inputs of a version.
+//! The layouts of the other arities are generated from `ENTRY_INPUTS`,
+//! the fields of every input or output, by `transfer_input_layout`
+//! and `reclaim_input_layout`.
+
+use super::*;
+use crate::multi::entry_inputs;
+use ark_ed_on_bls12_381::Fq;
+use ark_relations::r1cs::ToConstraintField;
+use ark_serialize::CanonicalDeserialize;
+use ark_std::vec::Vec;
+use manta_crypto::{CommitmentOutput, HashOutput};
+
+/// How a field of a payload is encoded into field elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEncoding {
+	/// A compressed Edwards point of 32 bytes, encoded as
+	/// its affine coordinates `x` then `y`.
+	CurvePoint,
+	/// A string of 32 bytes, packed into field elements of
+	/// 31 little-endian bytes each; the last element holds the last byte.
+	PackedBytes,
+	/// A `u64`, encoded as a single field element.
+	Scalar,
+}
+
+/// A field of a payload, and its place in the public inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputSegment {
+	/// The field of the payload, e.g., `sender_2.root`,
+	/// or `senders.root` in a generated layout.
+	pub field: &'static str,
+	/// The index of the input or output of the field in a generated layout,
+	/// e.g., `1` for the root of the second input.
+	pub index: Option<usize>,
+	/// How the field is encoded.
+	pub encoding: InputEncoding,
+	/// The number of field elements of the encoded field.
+	pub len: usize,
+}
+
+impl InputSegment {
+	const fn new(field: &'static str, encoding: InputEncoding) -> Self {
+		let len = match encoding {
+			InputEncoding::CurvePoint => 2,
+			InputEncoding::PackedBytes => 2,
+			InputEncoding::Scalar => 1,
+		};
+		Self {
+			field,
+			index: None,
+			encoding,
+			len,
+		}
+	}
+
+	const fn entry(field: &'static str, encoding: InputEncoding, index: usize) -> Self {
+		let segment = Self::new(field, encoding);
+		Self {
+			index: Some(index),
+			..segment
+		}
+	}
+}
+
+/// The inputs or the outputs of a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entries {
+	/// The inputs, `senders`.
+	Senders,
+	/// The outputs, `receivers`.
+	Receivers,
+}
+
+/// A field of every input or output of a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryInput {
+	/// The entries that have the field.
+	pub entries: Entries,
+	/// The field, as reported by `VerificationError::MalformedEntry`,
+	/// e.g., `senders.root`.
+	pub field: &'static str,
+	/// How the field is encoded.
+	pub encoding: InputEncoding,
+}
+
+/// The fields of the inputs and outputs in the public inputs, in order:
+/// the first field of every entry, then the second field of every entry,
+/// and so on.
+pub const ENTRY_INPUTS: &[EntryInput] = &[
+	EntryInput {
+		entries: Entries::Senders,
+		field: "senders.k",
+		encoding: InputEncoding::CurvePoint,
+	},
+	EntryInput {
+		entries: Entries::Receivers,
+		field: "receivers.cm",
+		encoding: InputEncoding::CurvePoint,
+	},
+	EntryInput {
+		entries: Entries::Senders,
+		field: "senders.void_number",
+		encoding: InputEncoding::PackedBytes,
+	},
+	EntryInput {
+		entries: Entries::Senders,
+		field: "senders.root",
+		encoding: InputEncoding::CurvePoint,
+	},
+];
+
+/// The public inputs of a private transfer after those of its entries.
+pub const TRANSFER_INPUT_TAIL: &[InputSegment] = &[
+	InputSegment::new("fee", InputEncoding::Scalar),
+	InputSegment::new("asset_id", InputEncoding::Scalar),
+	InputSegment::new("network_id", InputEncoding::Scalar),
+];
+
+/// The public inputs of a reclaim after those of its entries.
+pub const RECLAIM_INPUT_TAIL: &[InputSegment] = &[
+	InputSegment::new("reclaim_amount", InputEncoding::Scalar),
+	InputSegment::new("recipient", InputEncoding::PackedBytes),
+	InputSegment::new("asset_id", InputEncoding::Scalar),
+	InputSegment::new("network_id", InputEncoding::Scalar),
+];
+
+/// The public inputs of the private transfer circuit, in order;
+/// this is `transfer_input_layout` of the 2-in 2-out arity,
+/// with the fields named after those of `PrivateTransferData`.
+pub const PRIVATE_TRANSFER_INPUT_LAYOUT: &[InputSegment] = &[
+	InputSegment::new("sender_1.k", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.k", InputEncoding::CurvePoint),
+	InputSegment::new("receiver_1.cm", InputEncoding::CurvePoint),
+	InputSegment::new("receiver_2.cm", InputEncoding::CurvePoint),
+	InputSegment::new("sender_1.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_2.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_1.root", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.root", InputEncoding::CurvePoint),
+	InputSegment::new("fee", InputEncoding::Scalar),
+	InputSegment::new("asset_id", InputEncoding::Scalar),
+	InputSegment::new("network_id", InputEncoding::Scalar),
+];
+
+/// The public inputs of the private transfer circuit of `PayloadVersion::V0`,
+/// i.e., of the published `TRANSFER_PK`, in order: those of the entries
+/// of `PRIVATE_TRANSFER_INPUT_LAYOUT`.
+pub const PRIVATE_TRANSFER_INPUT_LAYOUT_V0: &[InputSegment] = &[
+	InputSegment::new("sender_1.k", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.k", InputEncoding::CurvePoint),
+	InputSegment::new("receiver_1.cm", InputEncoding::CurvePoint),
+	InputSegment::new("receiver_2.cm", InputEncoding::CurvePoint),
+	InputSegment::new("sender_1.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_2.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_1.root", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.root", InputEncoding::CurvePoint),
+];
+
+/// The public inputs of the reclaim circuit, in order;
+/// this is `reclaim_input_layout` of the 2-in 1-out arity,
+/// with the fields named after those of `ReclaimData`.
+pub const RECLAIM_INPUT_LAYOUT: &[InputSegment] = &[
+	InputSegment::new("sender_1.k", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.k", InputEncoding::CurvePoint),
+	InputSegment::new("receiver.cm", InputEncoding::CurvePoint),
+	InputSegment::new("sender_1.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_2.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_1.root", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.root", InputEncoding::CurvePoint),
+	InputSegment::new("reclaim_amount", InputEncoding::Scalar),
+	InputSegment::new("recipient", InputEncoding::PackedBytes),
+	InputSegment::new("asset_id", InputEncoding::Scalar),
+	InputSegment::new("network_id", InputEncoding::Scalar),
+];
+
+/// The public inputs of the reclaim circuit of `PayloadVersion::V0`,
+/// i.e., of the published `RECLAIM_PK`, in order: those of the entries
+/// of `RECLAIM_INPUT_LAYOUT`, the reclaimed amount and the asset id.
+pub const RECLAIM_INPUT_LAYOUT_V0: &[InputSegment] = &[
+	InputSegment::new("sender_1.k", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.k", InputEncoding::CurvePoint),
+	InputSegment::new("receiver.cm", InputEncoding::CurvePoint),
+	InputSegment::new("sender_1.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_2.void_number", InputEncoding::PackedBytes),
+	InputSegment::new("sender_1.root", InputEncoding::CurvePoint),
+	InputSegment::new("sender_2.root", InputEncoding::CurvePoint),
+	InputSegment::new("reclaim_amount", InputEncoding::Scalar),
+	InputSegment::new("asset_id", InputEncoding::Scalar),
+];
+
+/// The number of field elements described by a layout.
+pub fn input_len(layout: &[InputSegment]) -> usize {
+	layout.iter().map(|s| s.len).sum()
+}
+
+/// The public inputs of the transfer circuit of `arity`, in order.
+pub fn transfer_input_layout(arity: Arity) -> Vec<InputSegment> {
+	let mut layout = entry_layout(arity);
+	layout.extend_from_slice(TRANSFER_INPUT_TAIL);
+	layout
+}
+
+/// The public inputs of the reclaim circuit of `arity`, in order.
+pub fn reclaim_input_layout(arity: Arity) -> Vec<InputSegment> {
+	let mut layout = entry_layout(arity);
+	layout.extend_from_slice(RECLAIM_INPUT_TAIL);
+	layout
+}
+
+/// The public inputs of the entries of `arity`, as listed by `ENTRY_INPUTS`.
+fn entry_layout(arity: Arity) -> Vec<InputSegment> {
+	let mut layout = Vec::new();
+	for input in ENTRY_INPUTS {
+		let count = match input.entries {
+			Entries::Senders => arity.inputs,
+			Entries::Receivers => arity.outputs,
+		};
+		layout.extend((0..count).map(|i| InputSegment::entry(input.field, input.encoding, i)));
+	}
+	layout
+}
+
+impl PrivateTransferData {
+	/// The public inputs of the private transfer circuit,
+	/// as described by `PRIVATE_TRANSFER_INPUT_LAYOUT`.
+	/// They are those of the `MultiTransferData<2, 2>` of the transfer.
+	pub fn public_inputs(&self) -> Result<Vec<Fq>, VerificationError> {
+		MultiTransferData::<2, 2>::from(self.clone())
+			.public_inputs()
+			.map_err(|e| {
+				name_entry(
+					e,
+					MultiTransferData::<2, 2>::ARITY,
+					PRIVATE_TRANSFER_INPUT_LAYOUT,
+				)
+			})
+	}
+
+	/// The public inputs of the private transfer circuit of `version`,
+	/// as described by `version.private_transfer_input_layout()`.
+	/// The circuit of `V0` has no fee, so the fee must be zero.
+	pub fn public_inputs_of(&self, version: PayloadVersion) -> Result<Vec<Fq>, VerificationError> {
+		match version {
+			PayloadVersion::V0 => {
+				if self.fee != 0 {
+					return Err(VerificationError::UnboundField("fee"));
+				}
+				let layout = PRIVATE_TRANSFER_INPUT_LAYOUT_V0;
+				let mut inputs = Vec::with_capacity(input_len(layout));
+				entry_inputs(
+					&mut inputs,
+					&[self.sender_1, self.sender_2],
+					&[self.receiver_1, self.receiver_2],
+				)
+				.map_err(|e| name_entry(e, MultiTransferData::<2, 2>::ARITY, layout))?;
+				Ok(inputs)
+			}
+			PayloadVersion::V1 => self.public_inputs(),
+		}
+	}
+}
+
+impl<AccountId: ReclaimAccount> ReclaimData<AccountId> {
+	/// The public inputs of the reclaim circuit,
+	/// as described by `RECLAIM_INPUT_LAYOUT`.
+	/// They are those of the `MultiReclaimData<2, 1>` of the reclaim.
+	pub fn public_inputs(&self) -> Result<Vec<Fq>, VerificationError> {
+		MultiReclaimData::<2, 1, AccountId>::from(self.clone())
+			.public_inputs()
+			.map_err(|e| {
+				name_entry(
+					e,
+					MultiReclaimData::<2, 1, AccountId>::ARITY,
+					RECLAIM_INPUT_LAYOUT,
+				)
+			})
+	}
+
+	/// The public inputs of the reclaim circuit of `version`,
+	/// as described by `version.reclaim_input_layout()`.
+	/// The circuit of `V0` has no recipient, so the recipient must
+	/// encode to zeros.
+	pub fn public_inputs_of(&self, version: PayloadVersion) -> Result<Vec<Fq>, VerificationError> {
+		match version {
+			PayloadVersion::V0 => {
+				if self.recipient.encode_recipient() != [0u8; 32] {
+					return Err(VerificationError::UnboundField("recipient"));
+				}
+				let layout = RECLAIM_INPUT_LAYOUT_V0;
+				let mut inputs = Vec::with_capacity(input_len(layout));
+				entry_inputs(
+					&mut inputs,
+					&[self.sender_1, self.sender_2],
+					&[self.receiver],
+				)
+				.map_err(|e| name_entry(e, MultiReclaimData::<2, 1, AccountId>::ARITY, layout))?;
+				inputs.push(Fq::from(self.reclaim_amount));
+				inputs.push(Fq::from(self.asset_id as u64));
+				Ok(inputs)
+			}
+			PayloadVersion::V1 => self.public_inputs(),
+		}
+	}
+}
+
+/// Report a malformed entry of a payload of `arity` as the field at the
+/// same place in `layout`, e.g., `senders.root` at index `1` as `sender_2.root`.
+fn name_entry(
+	error: VerificationError,
+	arity: Arity,
+	layout: &[InputSegment],
+) -> VerificationError {
+	let (field, index) = match error {
+		VerificationError::MalformedEntry { field, index } => (field, index),
+		_ => return error,
+	};
+	entry_layout(arity)
+		.iter()
+		.position(|segment| segment.field == field && segment.index == Some(index))
+		.and_then(|position| layout.get(position))
+		.map_or(error, |segment| {
+			VerificationError::MalformedField(segment.field)
+		})
+}
+
+/// The public inputs of a commitment: the coordinates of the curve point,
+/// or `error` if the bytes are not a point.
+pub(crate) fn point_input_or(
+	bytes: &[u8; 32],
+	error: VerificationError,
+) -> Result<[Fq; 2], VerificationError> {
+	let cm = CommitmentOutput::deserialize(bytes.as_ref()).map_err(|_| error)?;
+	Ok([cm.x, cm.y])
+}
+
+/// The public inputs of a merkle root, or `error` if the bytes are not a root.
+pub(crate) fn root_input_or(
+	bytes: &[u8; 32],
+	error: VerificationError,
+) -> Result<Vec<Fq>, VerificationError> {
+	let root = HashOutput::deserialize(bytes.as_ref()).map_err(|_| error)?;
+	ToConstraintField::<Fq>::to_field_elements(&root).ok_or(error)
+}
+
+/// The public inputs of a byte string, packed into field elements,
+/// or `error` if the bytes cannot be packed.
+pub(crate) fn bytes_input_or(
+	bytes: &[u8; 32],
+	error: VerificationError,
+) -> Result<Vec<Fq>, VerificationError> {
+	ToConstraintField::<Fq>::to_field_elements(bytes.as_ref()).ok_or(error)
+}
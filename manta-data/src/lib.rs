@@ -16,20 +16,72 @@
 
 #![no_std]
 
+#[cfg(feature = "prover")]
+mod builder;
 mod default;
+mod error;
+mod id;
+mod inputs;
 mod metadata;
+#[cfg(feature = "mock-proofs")]
+mod mock;
+mod multi;
+mod planner;
+#[cfg(feature = "prover")]
+mod prover;
+#[cfg(feature = "prover")]
+mod relation;
 mod sanity;
 mod serdes;
+mod transaction;
+mod transition;
+mod verifier;
+mod version;
+mod witness;
 mod zkp;
 
-use manta_asset::{AssetId, MantaAsset};
-use manta_crypto::{AccountMembership, LedgerMerkleTreeRoot};
+use manta_asset::{AssetId, MantaAsset, NetworkId};
+use manta_crypto::LedgerMerkleTreeRoot;
 
+#[cfg(feature = "prover")]
+pub use builder::TransferBuilder;
+#[cfg(feature = "prover")]
+pub use error::BuildError;
+pub use error::{DecodeError, PlanError, TransitionError, VerificationError};
+pub use id::TransactionId;
+pub use inputs::{
+	input_len, reclaim_input_layout, transfer_input_layout, Entries, EntryInput, InputEncoding,
+	InputSegment, ENTRY_INPUTS, PRIVATE_TRANSFER_INPUT_LAYOUT, PRIVATE_TRANSFER_INPUT_LAYOUT_V0,
+	RECLAIM_INPUT_LAYOUT, RECLAIM_INPUT_LAYOUT_V0, RECLAIM_INPUT_TAIL, TRANSFER_INPUT_TAIL,
+};
 pub use metadata::BuildMetadata;
+#[cfg(feature = "mock-proofs")]
+pub use mock::{mock_prove_reclaim, mock_prove_transfer, MOCK_PROOF_MARKER, MOCK_VERIFICATION_KEY};
+pub use multi::{Arity, BatchTransferData, MultiReclaimData, MultiTransferData, VerificationKeys};
+pub use planner::{
+	plan, MinDust, MinTransactions, OldestFirst, PaymentKind, Plan, PlanInput, PlanStep,
+	SelectionStrategy,
+};
+#[cfg(feature = "prover")]
+pub use prover::{
+	prove_multi_reclaim, prove_multi_transfer, prove_reclaim, prove_transfer, Groth16Pk,
+	MultiReclaimWitness, MultiTransferWitness, ReclaimWitness, TransferWitness,
+};
+#[cfg(feature = "prover")]
+pub use relation::Relation;
+pub use transaction::{MantaTransaction, TRANSACTION_VERSION};
+pub use transition::FeeSink;
+pub use verifier::PreparedVerifier;
+pub use version::PayloadVersion;
+pub use witness::{IncrementalWitness, MembershipPath, TreeFrontier};
+#[cfg(test)]
+mod test;
 
-pub const MINT_PAYLOAD_SIZE: usize = 112;
-pub const PRIVATE_TRANSFER_PAYLOAD_SIZE: usize = 608;
-pub const RECLAIM_PAYLOAD_SIZE: usize = 512;
+/// The sizes of the payloads of `PayloadVersion::CURRENT`;
+/// `PayloadVersion` has the sizes of every version.
+pub const MINT_PAYLOAD_SIZE: usize = 116;
+pub const PRIVATE_TRANSFER_PAYLOAD_SIZE: usize = 628;
+pub const RECLAIM_PAYLOAD_SIZE: usize = 548;
 
 /// Type aliases
 pub type MintPayload = [u8; MINT_PAYLOAD_SIZE];
@@ -37,8 +89,12 @@ pub type PrivateTransferPayload = [u8; PRIVATE_TRANSFER_PAYLOAD_SIZE];
 pub type ReclaimPayload = [u8; RECLAIM_PAYLOAD_SIZE];
 
 /// Input data to a mint extrinsic.
+/// Every transaction carries the `network_id` of the network it is for;
+/// `MantaTransaction::try_verify` and the `apply` methods reject
+/// transactions for another network than the expected one.
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct MintData {
+	pub network_id: NetworkId,
 	pub asset_id: AssetId,
 	pub amount: u64,
 	pub cm: [u8; 32],
@@ -47,8 +103,17 @@ pub struct MintData {
 }
 
 /// Input data to a private transfer extrinsic.
+/// The network id and the asset id are public inputs of the proof,
+/// so that a transfer cannot be replayed on another network, and the
+/// runtime can apply per-asset rules to transfers.
+/// The inputs pay the outputs and a public `fee`, in the same asset,
+/// which the ledger credits to a `FeeSink`. The fee is a public input of
+/// `PayloadVersion::V1` only: a transfer of `V0` has no fee.
 #[derive(Debug, Clone, PartialEq)]
 pub struct PrivateTransferData {
+	pub network_id: NetworkId,
+	pub asset_id: AssetId,
+	pub fee: u64,
 	pub sender_1: SenderData,
 	pub sender_2: SenderData,
 	pub receiver_1: ReceiverData,
@@ -57,16 +122,44 @@ pub struct PrivateTransferData {
 }
 
 /// Input data to a reclaim extrinsic.
+/// The `recipient` of the reclaimed amount is a public input of the proof,
+/// so that the payload cannot be resubmitted for another account;
+/// so is the network id, as for private transfers.
+/// Both are public inputs of `PayloadVersion::V1` only: a reclaim of `V0`
+/// has no recipient, i.e., its recipient encodes to zeros.
 #[derive(Debug, Clone, PartialEq)]
-pub struct ReclaimData {
+pub struct ReclaimData<AccountId = [u8; 32]> {
+	pub network_id: NetworkId,
 	pub asset_id: AssetId,
 	pub reclaim_amount: u64,
+	pub recipient: AccountId,
 	pub sender_1: SenderData,
 	pub sender_2: SenderData,
 	pub receiver: ReceiverData,
 	pub proof: [u8; 192],
 }
 
+/// An account that receives the public funds of a reclaim, e.g., the
+/// `AccountId32` of a substrate runtime. The account is bound into the
+/// reclaim through its encoding of 32 bytes.
+pub trait ReclaimAccount: Clone {
+	/// The encoding of the account, as bound into the reclaim.
+	fn encode_recipient(&self) -> [u8; 32];
+
+	/// The account of an encoding, e.g., of a deserialized reclaim.
+	fn decode_recipient(bytes: [u8; 32]) -> Self;
+}
+
+impl ReclaimAccount for [u8; 32] {
+	fn encode_recipient(&self) -> [u8; 32] {
+		*self
+	}
+
+	fn decode_recipient(bytes: [u8; 32]) -> Self {
+		bytes
+	}
+}
+
 /// Data required for a sender to spend a coin.
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct SenderData {
@@ -76,6 +169,12 @@ pub struct SenderData {
 }
 
 /// Data required for a receiver to receive a coin.
+///
+/// Only `cm` is a public input of the proofs: the proofs do not bind `k`,
+/// `sender_pk` and `cipher`, so anyone who relays a payload can rewrite
+/// them without invalidating it. They are hints for the receiver, who must
+/// check the value and the `k` it recovers from them against `cm` before
+/// it spends the coin. For this reason, `TransactionId` leaves them out.
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct ReceiverData {
 	pub k: [u8; 32],
@@ -87,9 +186,11 @@ pub struct ReceiverData {
 /// A `SenderMetaData` is the data that a sender assembles from its `MantaAsset`
 /// and the current state of the ledger. This struct is an input to both
 /// `private transfer` and `reclaim` circuit.
+/// It is built from the leaves of the ledger with `BuildMetadata`, or kept
+/// up to date with an `IncrementalWitness`.
 #[derive(Debug, Clone, Default)]
 pub struct SenderMetaData {
 	pub asset: MantaAsset,
 	pub root: LedgerMerkleTreeRoot,
-	pub membership: AccountMembership,
+	pub membership: MembershipPath,
 }
@@ -15,9 +15,15 @@
 // along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::*;
-use ark_std::vec::Vec;
-use manta_crypto::{HashParam, LedgerMerkleTree};
+use ark_serialize::CanonicalSerialize;
+use ark_std::{
+	rand::{CryptoRng, RngCore},
+	vec::Vec,
+};
+use manta_asset::{MantaAssetProcessedReceiver, Sampling};
+use manta_crypto::{CommitmentParam, HashParam};
 use manta_error::MantaError;
+use manta_ledger::SHARD_CAPACITY;
 
 pub trait BuildMetadata {
 	type Param;
@@ -37,27 +43,74 @@ impl BuildMetadata for MantaAsset {
 	type Metadata = SenderMetaData;
 
 	/// Build the `SenderMetaData` from sender's `MantaAsset`
-	/// and the current state of the ledger.
+	/// and the leaves of the current tree of its shard.
+	/// This replays the whole tree; an `IncrementalWitness` of the asset
+	/// builds the same metadata as the ledger grows, at a lower cost.
 	fn build(
 		&self,
 		param: &Self::Param,
 		leaves: &Self::Ledger,
 	) -> Result<Self::Metadata, MantaError> {
-		let tree = LedgerMerkleTree::new(param.clone(), &leaves)?;
-		let root = tree.root();
-
-		let index = match leaves.iter().position(|x| *x == self.utxo) {
-			Some(p) => p,
-			None => {
-				return Err(MantaError::LeavesNotFound);
-			}
-		};
-		let membership = tree.generate_proof(index, &self.utxo)?;
-
-		Ok(SenderMetaData {
-			asset: self.clone(),
+		let witness = IncrementalWitness::from_leaves(param, self, leaves, 0, SHARD_CAPACITY)?;
+		witness.sender_metadata(param)
+	}
+}
+
+impl SenderMetaData {
+	/// The `SenderData` that is posted to the ledger when
+	/// the asset of this metadata is spent.
+	pub fn sender_data(&self) -> Result<SenderData, MantaError> {
+		let mut root = [0u8; 32];
+		self.root.serialize(root.as_mut())?;
+
+		Ok(SenderData {
+			k: self.asset.pub_info.k,
+			void_number: self.asset.void_number,
+			root,
+		})
+	}
+
+	/// Sample a dummy input: a zero-value asset of `asset_id` under a fresh
+	/// secret key, to pad a transaction that spends a single real asset.
+	///
+	/// The circuits do not check the membership of a zero-value asset, so
+	/// a dummy is never posted to the ledger and its `membership` is the
+	/// default path. Its `root` is still checked against the ledger like
+	/// any other root, so it has to be a root that the ledger accepts;
+	/// the root of the real input of the transaction is the convention.
+	/// The void number of a dummy is random, so that it never collides
+	/// with the void number of another asset.
+	pub fn dummy<R: RngCore + CryptoRng>(
+		commit_param: &CommitmentParam,
+		asset_id: AssetId,
+		root: LedgerMerkleTreeRoot,
+		rng: &mut R,
+	) -> Result<Self, MantaError> {
+		let mut sk = [0u8; 32];
+		rng.fill_bytes(&mut sk);
+
+		Ok(Self {
+			asset: MantaAsset::sample(commit_param, &sk, &asset_id, &0, rng)?,
 			root,
-			membership,
+			membership: MembershipPath::default(),
 		})
 	}
+
+	/// Whether this is a dummy input, i.e., an asset of value zero,
+	/// whose membership is not checked by the circuits.
+	pub fn is_dummy(&self) -> bool {
+		self.asset.priv_info.value == 0
+	}
+}
+
+impl From<&MantaAssetProcessedReceiver> for ReceiverData {
+	/// The `ReceiverData` that is posted to the ledger for a processed receiver.
+	fn from(receiver: &MantaAssetProcessedReceiver) -> Self {
+		Self {
+			k: receiver.prepared_data.k,
+			cm: receiver.utxo,
+			sender_pk: receiver.sender_pk,
+			cipher: receiver.ciphertext,
+		}
+	}
 }
@@ -0,0 +1,108 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a stand-in for the Groth16 prover and verifier,
+//! for integration tests that cannot wait for real proofs.
+//! It is only available with the `mock-proofs` feature.
+//!
+//! The mock prover checks, natively, the relations that the circuits
+//! enforce, and emits a marker proof that binds the public inputs.
+//! Every verifier, i.e., `MantaZKPVerifier::verify`, `try_verify`,
+//! `verify_with` and `PreparedVerifier` with its batches, checks marker
+//! proofs when it is given `MOCK_VERIFICATION_KEY`, and Groth16 proofs
+//! for any other key. The mock key is not a valid Groth16 key, and marker proofs
+//! are not valid Groth16 proofs, so enabling this feature does not weaken
+//! the real keys; tests switch verifiers by switching keys.
+
+use super::*;
+use crate::prover::{reclaim_data, transfer_data};
+use ark_ed_on_bls12_381::Fq;
+use ark_serialize::CanonicalSerialize;
+use ark_std::vec::Vec;
+use blake2::{Blake2s, Digest};
+use manta_crypto::VerificationKey;
+use manta_error::MantaError;
+
+/// The first bytes of a marker proof.
+pub const MOCK_PROOF_MARKER: [u8; 16] = *b"manta-mock-proof";
+
+/// The verification key of marker proofs.
+pub const MOCK_VERIFICATION_KEY: VerificationKey = VerificationKey {
+	data: b"manta-mock-verification-key",
+};
+
+/// Whether `key` is `MOCK_VERIFICATION_KEY`.
+pub(crate) fn is_mock_key(key: &VerificationKey) -> bool {
+	key.data == MOCK_VERIFICATION_KEY.data
+}
+
+/// Check that `proof` is the marker proof of `inputs`.
+pub(crate) fn check_marker_proof(
+	proof: &[u8; 192],
+	inputs: &[Fq],
+) -> Result<(), VerificationError> {
+	if *proof == marker_proof(inputs) {
+		Ok(())
+	} else {
+		Err(VerificationError::ProofRejected)
+	}
+}
+
+/// Build a private transfer with a marker proof, if the witness
+/// satisfies the relations of the transfer circuit;
+/// otherwise, report the violated relations.
+pub fn mock_prove_transfer(witness: TransferWitness) -> Result<PrivateTransferData, BuildError> {
+	witness.check_relations().map_err(BuildError::Relations)?;
+
+	let mut data = transfer_data(&witness)?;
+	data.proof = marker_proof(
+		&data
+			.public_inputs()
+			.map_err(|_| MantaError::SanityCheckFail)?,
+	);
+	Ok(data)
+}
+
+/// Build a reclaim with a marker proof, if the witness
+/// satisfies the relations of the reclaim circuit;
+/// otherwise, report the violated relations.
+pub fn mock_prove_reclaim(witness: ReclaimWitness) -> Result<ReclaimData, BuildError> {
+	witness.check_relations().map_err(BuildError::Relations)?;
+
+	let mut data = reclaim_data(&witness)?;
+	data.proof = marker_proof(
+		&data
+			.public_inputs()
+			.map_err(|_| MantaError::SanityCheckFail)?,
+	);
+	Ok(data)
+}
+
+/// The marker proof of `inputs`: the marker, followed by
+/// the Blake2s digest of the serialized public inputs.
+fn marker_proof(inputs: &[Fq]) -> [u8; 192] {
+	let mut buf = Vec::new();
+	for input in inputs {
+		input
+			.serialize(&mut buf)
+			.unwrap_or_else(|_| panic!("serializing into a vector does not fail; qed"));
+	}
+
+	let mut proof = [0u8; 192];
+	proof[..16].copy_from_slice(&MOCK_PROOF_MARKER);
+	proof[16..48].copy_from_slice(&Blake2s::digest(&buf));
+	proof
+}
@@ -0,0 +1,467 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements private transfers and reclaims of any arity:
+//! `N` inputs and `M` outputs, e.g., 4-in 4-out transfers for batching.
+//!
+//! The payloads and the public inputs generalize those of the 2-in 2-out
+//! transfer and the 2-in 1-out reclaim: the public inputs are the `k` of
+//! every input, the `cm` of every output, the void number of every input
+//! and the root of every input, in this order, followed by the fee for a
+//! transfer or the reclaimed amount and its recipient for a reclaim, and
+//! the asset id and the network id. So `MultiTransferData<2, 2>` and
+//! `MultiReclaimData<2, 1>` have the same payload and public inputs as
+//! `PrivateTransferData` and `ReclaimData`, which are implemented by
+//! converting them into these.
+//!
+//! Every arity is a different circuit, with its own verification key;
+//! `VerificationKeys` holds the keys of the supported arities, and checks
+//! that every key has the public inputs of its arity. The
+//! circuits of every arity are in `manta-circuits`, and are proven with
+//! `prove_multi_transfer` and `prove_multi_reclaim`.
+
+use super::*;
+use crate::inputs::{
+	bytes_input_or, input_len, point_input_or, reclaim_input_layout, root_input_or,
+	transfer_input_layout,
+};
+use ark_ed_on_bls12_381::Fq;
+use ark_std::{
+	io::{Read, Write},
+	vec::Vec,
+};
+use manta_crypto::{MantaSerDes, MantaZKPVerifier, VerificationKey};
+use manta_error::MantaError;
+
+/// The size of a serialized `SenderData`.
+const SENDER_DATA_SIZE: usize = 96;
+/// The size of a serialized `ReceiverData`.
+const RECEIVER_DATA_SIZE: usize = 112;
+/// The number of public inputs of an input: its `k`, void number and root.
+const SENDER_INPUT_LEN: usize = 6;
+/// The number of public inputs of an output: its `cm`.
+const RECEIVER_INPUT_LEN: usize = 2;
+
+/// The number of inputs and outputs of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+	pub inputs: usize,
+	pub outputs: usize,
+}
+
+/// Input data to a private transfer of `N` inputs and `M` outputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiTransferData<const N: usize, const M: usize> {
+	pub network_id: NetworkId,
+	pub asset_id: AssetId,
+	pub fee: u64,
+	pub senders: [SenderData; N],
+	pub receivers: [ReceiverData; M],
+	pub proof: [u8; 192],
+}
+
+/// Input data to a reclaim of `N` inputs and `M` outputs,
+/// to the account `recipient`, as for `ReclaimData`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiReclaimData<const N: usize, const M: usize, AccountId = [u8; 32]> {
+	pub network_id: NetworkId,
+	pub asset_id: AssetId,
+	pub reclaim_amount: u64,
+	pub recipient: AccountId,
+	pub senders: [SenderData; N],
+	pub receivers: [ReceiverData; M],
+	pub proof: [u8; 192],
+}
+
+/// A 4-in 4-out private transfer, for batching.
+pub type BatchTransferData = MultiTransferData<4, 4>;
+
+impl<const N: usize, const M: usize> MultiTransferData<N, M> {
+	/// The arity of the transfer.
+	pub const ARITY: Arity = Arity {
+		inputs: N,
+		outputs: M,
+	};
+
+	/// The size of the serialized transfer.
+	pub const PAYLOAD_SIZE: usize = 20 + N * SENDER_DATA_SIZE + M * RECEIVER_DATA_SIZE + 192;
+
+	/// The number of public inputs of the transfer circuit.
+	pub const PUBLIC_INPUT_LEN: usize = N * SENDER_INPUT_LEN + M * RECEIVER_INPUT_LEN + 3;
+
+	/// The public inputs of the transfer circuit.
+	pub fn public_inputs(&self) -> Result<Vec<Fq>, VerificationError> {
+		let mut inputs = Vec::with_capacity(Self::PUBLIC_INPUT_LEN);
+		entry_inputs(&mut inputs, &self.senders, &self.receivers)?;
+		inputs.push(Fq::from(self.fee));
+		inputs.push(Fq::from(self.asset_id as u64));
+		inputs.push(Fq::from(self.network_id));
+		Ok(inputs)
+	}
+
+	/// This algorithm verifies the ZKP, given the verification key
+	/// of the arity of the transfer and the data.
+	/// With the `mock-proofs` feature, `MOCK_VERIFICATION_KEY` checks the
+	/// marker proof of the data instead.
+	pub fn try_verify(&self, key: &VerificationKey) -> Result<(), VerificationError> {
+		PreparedVerifier::new_unchecked(key, PayloadVersion::CURRENT)?
+			.verify_proof(&self.proof, &self.public_inputs()?)
+	}
+
+	/// This algorithm verifies the ZKP with the key of its arity in `keys`.
+	/// A key that does not have the public inputs of the arity is rejected
+	/// with `KeyMismatch`.
+	pub fn verify_with(&self, keys: &VerificationKeys) -> Result<(), VerificationError> {
+		let key = keys
+			.transfer_key(Self::ARITY)
+			.ok_or(VerificationError::UnsupportedArity {
+				inputs: N,
+				outputs: M,
+			})?;
+		self.try_verify(key)
+	}
+}
+
+impl<const N: usize, const M: usize, AccountId: ReclaimAccount> MultiReclaimData<N, M, AccountId> {
+	/// The arity of the reclaim.
+	pub const ARITY: Arity = Arity {
+		inputs: N,
+		outputs: M,
+	};
+
+	/// The size of the serialized reclaim.
+	pub const PAYLOAD_SIZE: usize = 52 + N * SENDER_DATA_SIZE + M * RECEIVER_DATA_SIZE + 192;
+
+	/// The number of public inputs of the reclaim circuit.
+	pub const PUBLIC_INPUT_LEN: usize = N * SENDER_INPUT_LEN + M * RECEIVER_INPUT_LEN + 5;
+
+	/// The public inputs of the reclaim circuit.
+	pub fn public_inputs(&self) -> Result<Vec<Fq>, VerificationError> {
+		let mut inputs = Vec::with_capacity(Self::PUBLIC_INPUT_LEN);
+		entry_inputs(&mut inputs, &self.senders, &self.receivers)?;
+		inputs.push(Fq::from(self.reclaim_amount));
+		inputs.extend(bytes_input_or(
+			&self.recipient.encode_recipient(),
+			VerificationError::MalformedField("recipient"),
+		)?);
+		inputs.push(Fq::from(self.asset_id as u64));
+		inputs.push(Fq::from(self.network_id));
+		Ok(inputs)
+	}
+
+	/// This algorithm verifies the ZKP, given the verification key
+	/// of the arity of the reclaim and the data.
+	/// With the `mock-proofs` feature, `MOCK_VERIFICATION_KEY` checks the
+	/// marker proof of the data instead.
+	pub fn try_verify(&self, key: &VerificationKey) -> Result<(), VerificationError> {
+		PreparedVerifier::new_unchecked(key, PayloadVersion::CURRENT)?
+			.verify_proof(&self.proof, &self.public_inputs()?)
+	}
+
+	/// This algorithm verifies the ZKP with the key of its arity in `keys`.
+	/// A key that does not have the public inputs of the arity is rejected
+	/// with `KeyMismatch`.
+	pub fn verify_with(&self, keys: &VerificationKeys) -> Result<(), VerificationError> {
+		let key = keys
+			.reclaim_key(Self::ARITY)
+			.ok_or(VerificationError::UnsupportedArity {
+				inputs: N,
+				outputs: M,
+			})?;
+		self.try_verify(key)
+	}
+}
+
+/// Append the public inputs of `senders` and `receivers` to `inputs`.
+pub(crate) fn entry_inputs(
+	inputs: &mut Vec<Fq>,
+	senders: &[SenderData],
+	receivers: &[ReceiverData],
+) -> Result<(), VerificationError> {
+	// the fields of `ENTRY_INPUTS`, in order
+	let error = |field, index| VerificationError::MalformedEntry { field, index };
+	for (i, sender) in senders.iter().enumerate() {
+		inputs.extend_from_slice(&point_input_or(&sender.k, error("senders.k", i))?);
+	}
+	for (i, receiver) in receivers.iter().enumerate() {
+		inputs.extend_from_slice(&point_input_or(&receiver.cm, error("receivers.cm", i))?);
+	}
+	for (i, sender) in senders.iter().enumerate() {
+		inputs.extend(bytes_input_or(
+			&sender.void_number,
+			error("senders.void_number", i),
+		)?);
+	}
+	for (i, sender) in senders.iter().enumerate() {
+		inputs.extend(root_input_or(&sender.root, error("senders.root", i))?);
+	}
+	Ok(())
+}
+
+impl<const N: usize, const M: usize> Default for MultiTransferData<N, M> {
+	fn default() -> Self {
+		Self {
+			network_id: NetworkId::default(),
+			asset_id: AssetId::default(),
+			fee: 0,
+			senders: [SenderData::default(); N],
+			receivers: [ReceiverData::default(); M],
+			proof: [0u8; 192],
+		}
+	}
+}
+
+impl<const N: usize, const M: usize, AccountId: ReclaimAccount> Default
+	for MultiReclaimData<N, M, AccountId>
+{
+	fn default() -> Self {
+		Self {
+			network_id: NetworkId::default(),
+			asset_id: AssetId::default(),
+			reclaim_amount: 0,
+			recipient: AccountId::decode_recipient([0u8; 32]),
+			senders: [SenderData::default(); N],
+			receivers: [ReceiverData::default(); M],
+			proof: [0u8; 192],
+		}
+	}
+}
+
+impl<const N: usize, const M: usize> MantaZKPVerifier for MultiTransferData<N, M> {
+	type VerificationKey = VerificationKey;
+
+	/// This algorithm verifies the ZKP, given the verification key and the data.
+	fn verify(&self, key: &VerificationKey) -> bool {
+		self.try_verify(key).is_ok()
+	}
+}
+
+impl<const N: usize, const M: usize, AccountId: ReclaimAccount> MantaZKPVerifier
+	for MultiReclaimData<N, M, AccountId>
+{
+	type VerificationKey = VerificationKey;
+
+	/// This algorithm verifies the ZKP, given the verification key and the data.
+	fn verify(&self, key: &VerificationKey) -> bool {
+		self.try_verify(key).is_ok()
+	}
+}
+
+impl<const N: usize, const M: usize> MantaSerDes for MultiTransferData<N, M> {
+	/// Serialize the transfer into `PAYLOAD_SIZE` bytes.
+	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
+		writer.write_all(&self.network_id.to_le_bytes())?;
+		writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
+		writer.write_all(self.fee.to_le_bytes().as_ref())?;
+		for sender in self.senders.iter() {
+			sender.serialize(&mut writer)?;
+		}
+		for receiver in self.receivers.iter() {
+			receiver.serialize(&mut writer)?;
+		}
+		writer.write_all(&self.proof.as_ref()).map_err(|e| e.into())
+	}
+
+	/// Deserialize `PAYLOAD_SIZE` bytes into a transfer.
+	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
+		let mut data = Self::default();
+
+		let mut buf = [0u8; 4];
+		reader.read_exact(buf.as_mut())?;
+		data.network_id = u32::from_le_bytes(buf);
+
+		let mut buf = [0u8; 8];
+		reader.read_exact(buf.as_mut())?;
+		data.asset_id = u64::from_le_bytes(buf);
+
+		reader.read_exact(buf.as_mut())?;
+		data.fee = u64::from_le_bytes(buf);
+
+		for sender in data.senders.iter_mut() {
+			*sender = SenderData::deserialize(&mut reader)?;
+		}
+		for receiver in data.receivers.iter_mut() {
+			*receiver = ReceiverData::deserialize(&mut reader)?;
+		}
+		reader.read_exact(&mut data.proof)?;
+		Ok(data)
+	}
+}
+
+impl<const N: usize, const M: usize, AccountId: ReclaimAccount> MantaSerDes
+	for MultiReclaimData<N, M, AccountId>
+{
+	/// Serialize the reclaim into `PAYLOAD_SIZE` bytes.
+	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
+		writer.write_all(&self.network_id.to_le_bytes())?;
+		writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
+		writer.write_all(self.reclaim_amount.to_le_bytes().as_ref())?;
+		writer.write_all(&self.recipient.encode_recipient())?;
+		for sender in self.senders.iter() {
+			sender.serialize(&mut writer)?;
+		}
+		for receiver in self.receivers.iter() {
+			receiver.serialize(&mut writer)?;
+		}
+		writer.write_all(&self.proof.as_ref()).map_err(|e| e.into())
+	}
+
+	/// Deserialize `PAYLOAD_SIZE` bytes into a reclaim.
+	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
+		let mut data = Self::default();
+
+		let mut buf = [0u8; 4];
+		reader.read_exact(buf.as_mut())?;
+		data.network_id = u32::from_le_bytes(buf);
+
+		let mut buf = [0u8; 8];
+		reader.read_exact(buf.as_mut())?;
+		data.asset_id = u64::from_le_bytes(buf);
+
+		reader.read_exact(buf.as_mut())?;
+		data.reclaim_amount = u64::from_le_bytes(buf);
+
+		let mut recipient = [0u8; 32];
+		reader.read_exact(&mut recipient)?;
+		data.recipient = AccountId::decode_recipient(recipient);
+
+		for sender in data.senders.iter_mut() {
+			*sender = SenderData::deserialize(&mut reader)?;
+		}
+		for receiver in data.receivers.iter_mut() {
+			*receiver = ReceiverData::deserialize(&mut reader)?;
+		}
+		reader.read_exact(&mut data.proof)?;
+		Ok(data)
+	}
+}
+
+impl From<PrivateTransferData> for MultiTransferData<2, 2> {
+	fn from(data: PrivateTransferData) -> Self {
+		Self {
+			network_id: data.network_id,
+			asset_id: data.asset_id,
+			fee: data.fee,
+			senders: [data.sender_1, data.sender_2],
+			receivers: [data.receiver_1, data.receiver_2],
+			proof: data.proof,
+		}
+	}
+}
+
+impl From<MultiTransferData<2, 2>> for PrivateTransferData {
+	fn from(data: MultiTransferData<2, 2>) -> Self {
+		let [sender_1, sender_2] = data.senders;
+		let [receiver_1, receiver_2] = data.receivers;
+		Self {
+			network_id: data.network_id,
+			asset_id: data.asset_id,
+			fee: data.fee,
+			sender_1,
+			sender_2,
+			receiver_1,
+			receiver_2,
+			proof: data.proof,
+		}
+	}
+}
+
+impl<AccountId> From<ReclaimData<AccountId>> for MultiReclaimData<2, 1, AccountId> {
+	fn from(data: ReclaimData<AccountId>) -> Self {
+		Self {
+			network_id: data.network_id,
+			asset_id: data.asset_id,
+			reclaim_amount: data.reclaim_amount,
+			recipient: data.recipient,
+			senders: [data.sender_1, data.sender_2],
+			receivers: [data.receiver],
+			proof: data.proof,
+		}
+	}
+}
+
+impl<AccountId> From<MultiReclaimData<2, 1, AccountId>> for ReclaimData<AccountId> {
+	fn from(data: MultiReclaimData<2, 1, AccountId>) -> Self {
+		let [sender_1, sender_2] = data.senders;
+		let [receiver] = data.receivers;
+		Self {
+			network_id: data.network_id,
+			asset_id: data.asset_id,
+			reclaim_amount: data.reclaim_amount,
+			recipient: data.recipient,
+			sender_1,
+			sender_2,
+			receiver,
+			proof: data.proof,
+		}
+	}
+}
+
+/// The verification keys of the supported arities of transfers and reclaims.
+#[derive(Default)]
+pub struct VerificationKeys {
+	transfer: Vec<(Arity, VerificationKey)>,
+	reclaim: Vec<(Arity, VerificationKey)>,
+}
+
+impl VerificationKeys {
+	/// Support transfers of `arity`, verified with `key`;
+	/// this replaces the previous key of `arity`, if any.
+	/// A key that does not have the public inputs of `arity` is rejected.
+	pub fn insert_transfer(
+		&mut self,
+		arity: Arity,
+		key: VerificationKey,
+	) -> Result<(), VerificationError> {
+		PreparedVerifier::new_unchecked(&key, PayloadVersion::CURRENT)?
+			.check_input_len(input_len(&transfer_input_layout(arity)))?;
+		insert_key(&mut self.transfer, arity, key);
+		Ok(())
+	}
+
+	/// Support reclaims of `arity`, verified with `key`;
+	/// this replaces the previous key of `arity`, if any.
+	/// A key that does not have the public inputs of `arity` is rejected.
+	pub fn insert_reclaim(
+		&mut self,
+		arity: Arity,
+		key: VerificationKey,
+	) -> Result<(), VerificationError> {
+		PreparedVerifier::new_unchecked(&key, PayloadVersion::CURRENT)?
+			.check_input_len(input_len(&reclaim_input_layout(arity)))?;
+		insert_key(&mut self.reclaim, arity, key);
+		Ok(())
+	}
+
+	/// The key of the transfers of `arity`, if they are supported.
+	pub fn transfer_key(&self, arity: Arity) -> Option<&VerificationKey> {
+		find_key(&self.transfer, arity)
+	}
+
+	/// The key of the reclaims of `arity`, if they are supported.
+	pub fn reclaim_key(&self, arity: Arity) -> Option<&VerificationKey> {
+		find_key(&self.reclaim, arity)
+	}
+}
+
+fn insert_key(keys: &mut Vec<(Arity, VerificationKey)>, arity: Arity, key: VerificationKey) {
+	keys.retain(|(a, _)| *a != arity);
+	keys.push((arity, key));
+}
+
+fn find_key(keys: &[(Arity, VerificationKey)], arity: Arity) -> Option<&VerificationKey> {
+	keys.iter().find(|(a, _)| *a == arity).map(|(_, key)| key)
+}
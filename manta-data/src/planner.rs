@@ -0,0 +1,210 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a planner of payments for the 2-in 2-out
+//! shape of private transfers and reclaims.
+//!
+//! A payment that needs more than two notes is planned as a chain of
+//! merge transfers, each of which spends two notes into a single note
+//! of the sender, followed by the final payment.
+//! A `SelectionStrategy` decides which notes are spent first; the
+//! planner spends notes in that order until they cover the amount and
+//! the fee of the payment.
+//! A plan only describes the transactions; it does not prove anything.
+
+use super::*;
+use ark_std::{convert::TryFrom, vec::Vec};
+
+/// The kind of the final transaction of a plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentKind {
+	/// A private transfer to a recipient.
+	Transfer,
+	/// A reclaim out of the private ledger.
+	Reclaim,
+}
+
+/// An input of a planned transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanInput {
+	/// The owned note at this position of the planned assets.
+	Owned(usize),
+	/// The note created by the merge at this position of the plan.
+	Merged(usize),
+	/// A zero-value dummy note, to pad a single input.
+	Dummy,
+}
+
+/// A transaction of a plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanStep {
+	/// A private transfer of two notes to a single note of the sender of
+	/// `value`; the second output is a zero-value note.
+	Merge { inputs: [PlanInput; 2], value: u64 },
+	/// The final payment of `amount` and of a public `fee`, where `change`
+	/// goes back to the sender.
+	Payment {
+		kind: PaymentKind,
+		inputs: [PlanInput; 2],
+		amount: u64,
+		fee: u64,
+		change: u64,
+	},
+}
+
+/// A sequence of merges, followed by the final payment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+	pub steps: Vec<PlanStep>,
+}
+
+impl Plan {
+	/// The number of transactions of the plan.
+	pub fn transaction_count(&self) -> usize {
+		self.steps.len()
+	}
+
+	/// The positions of the owned notes that the plan spends.
+	pub fn spent_notes(&self) -> Vec<usize> {
+		self.steps
+			.iter()
+			.flat_map(|step| match step {
+				PlanStep::Merge { inputs, .. } | PlanStep::Payment { inputs, .. } => inputs.iter(),
+			})
+			.filter_map(|input| match input {
+				PlanInput::Owned(i) => Some(*i),
+				_ => None,
+			})
+			.collect()
+	}
+}
+
+/// A strategy that decides in which order notes are spent.
+pub trait SelectionStrategy {
+	/// The positions of the `candidates` notes of `assets`, in the order
+	/// in which they are spent.
+	fn order(&self, assets: &[MantaAsset], candidates: Vec<usize>) -> Vec<usize>;
+}
+
+/// Spend the largest notes first, which minimizes the number of notes
+/// spent, hence the number of transactions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinTransactions;
+
+impl SelectionStrategy for MinTransactions {
+	fn order(&self, assets: &[MantaAsset], mut candidates: Vec<usize>) -> Vec<usize> {
+		candidates.sort_by_key(|&i| ark_std::cmp::Reverse(assets[i].priv_info.value));
+		candidates
+	}
+}
+
+/// Spend the smallest notes first, which consolidates the dust of
+/// the wallet into the change of the payment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinDust;
+
+impl SelectionStrategy for MinDust {
+	fn order(&self, assets: &[MantaAsset], mut candidates: Vec<usize>) -> Vec<usize> {
+		candidates.sort_by_key(|&i| assets[i].priv_info.value);
+		candidates
+	}
+}
+
+/// Spend the oldest notes first; the planned assets are expected
+/// in the order in which they were received.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OldestFirst;
+
+impl SelectionStrategy for OldestFirst {
+	fn order(&self, _assets: &[MantaAsset], candidates: Vec<usize>) -> Vec<usize> {
+		candidates
+	}
+}
+
+/// Plan a payment of `amount` of `asset_id`, plus a public `fee`, out
+/// of `assets`, spending notes in the order of `strategy`.
+/// A reclaim carries no fee, so `fee` must be zero for a reclaim.
+pub fn plan<S: SelectionStrategy>(
+	assets: &[MantaAsset],
+	asset_id: AssetId,
+	amount: u64,
+	fee: u64,
+	kind: PaymentKind,
+	strategy: &S,
+) -> Result<Plan, PlanError> {
+	if kind == PaymentKind::Reclaim && fee != 0 {
+		return Err(PlanError::ReclaimFee);
+	}
+	let cost = amount.checked_add(fee).ok_or(PlanError::ValueOverflow)?;
+	let candidates = (0..assets.len())
+		.filter(|&i| assets[i].asset_id == asset_id && assets[i].priv_info.value > 0)
+		.collect();
+
+	// spend notes until they cover the amount and the fee
+	let mut selected = Vec::new();
+	let mut available = 0u128;
+	for i in strategy.order(assets, candidates) {
+		if available >= cost as u128 && !selected.is_empty() {
+			break;
+		}
+		selected.push(i);
+		available += assets[i].priv_info.value as u128;
+	}
+	if available < cost as u128 || selected.is_empty() {
+		return Err(PlanError::InsufficientFunds {
+			available,
+			total: cost,
+		});
+	}
+
+	// merge the notes, one at a time, until two of them are left
+	let last = if selected.len() > 1 {
+		selected.pop()
+	} else {
+		None
+	};
+	let mut steps = Vec::new();
+	let mut carry = (
+		PlanInput::Owned(selected[0]),
+		assets[selected[0]].priv_info.value,
+	);
+	for &i in &selected[1..] {
+		let value = carry
+			.1
+			.checked_add(assets[i].priv_info.value)
+			.ok_or(PlanError::ValueOverflow)?;
+		steps.push(PlanStep::Merge {
+			inputs: [carry.0, PlanInput::Owned(i)],
+			value,
+		});
+		carry = (PlanInput::Merged(steps.len() - 1), value);
+	}
+
+	let (second, second_value) = match last {
+		Some(i) => (PlanInput::Owned(i), assets[i].priv_info.value),
+		None => (PlanInput::Dummy, 0),
+	};
+	let change = u64::try_from(carry.1 as u128 + second_value as u128 - cost as u128)
+		.map_err(|_| PlanError::ValueOverflow)?;
+	steps.push(PlanStep::Payment {
+		kind,
+		inputs: [carry.0, second],
+		amount,
+		fee,
+		change,
+	});
+	Ok(Plan { steps })
+}
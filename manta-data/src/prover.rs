@@ -0,0 +1,261 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the prover side of private transfers and reclaims.
+//! It is only available with the `prover` feature.
+//!
+//! The constraint systems are supplied by the caller as any circuit that
+//! can be built from a `TransferWitness` or a `ReclaimWitness`, or from
+//! their counterparts of any arity; the prover builds the proof, and fills
+//! in the `SenderData` and `ReceiverData` that go along with it.
+
+use super::*;
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::Fq;
+use ark_groth16::{create_random_proof, ProvingKey};
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::{CryptoRng, RngCore};
+use manta_asset::MantaAssetProcessedReceiver;
+use manta_crypto::{CommitmentParam, HashParam};
+use manta_error::MantaError;
+
+/// A Groth16 proving key over BLS12-381.
+pub type Groth16Pk = ProvingKey<Bls12_381>;
+
+/// The witness of a private transfer: two assets to spend,
+/// two receivers to create, the fee that is paid, and the
+/// network that the transfer is for.
+#[derive(Clone)]
+pub struct TransferWitness {
+	pub commit_param: CommitmentParam,
+	pub hash_param: HashParam,
+	pub sender_1: SenderMetaData,
+	pub sender_2: SenderMetaData,
+	pub receiver_1: MantaAssetProcessedReceiver,
+	pub receiver_2: MantaAssetProcessedReceiver,
+	pub fee: u64,
+	pub network_id: NetworkId,
+}
+
+/// The witness of a reclaim: two assets to spend, a receiver to
+/// create with the change, the amount that is reclaimed, the
+/// 32-byte encoding of the account that receives it, and the network
+/// that the reclaim is for.
+#[derive(Clone)]
+pub struct ReclaimWitness {
+	pub commit_param: CommitmentParam,
+	pub hash_param: HashParam,
+	pub sender_1: SenderMetaData,
+	pub sender_2: SenderMetaData,
+	pub receiver: MantaAssetProcessedReceiver,
+	pub reclaim_amount: u64,
+	pub recipient: [u8; 32],
+	pub network_id: NetworkId,
+}
+
+/// The witness of a private transfer of `N` inputs and `M` outputs,
+/// as for a `TransferWitness`.
+#[derive(Clone)]
+pub struct MultiTransferWitness<const N: usize, const M: usize> {
+	pub commit_param: CommitmentParam,
+	pub hash_param: HashParam,
+	pub senders: [SenderMetaData; N],
+	pub receivers: [MantaAssetProcessedReceiver; M],
+	pub fee: u64,
+	pub network_id: NetworkId,
+}
+
+/// The witness of a reclaim of `N` inputs and `M` outputs,
+/// as for a `ReclaimWitness`.
+#[derive(Clone)]
+pub struct MultiReclaimWitness<const N: usize, const M: usize> {
+	pub commit_param: CommitmentParam,
+	pub hash_param: HashParam,
+	pub senders: [SenderMetaData; N],
+	pub receivers: [MantaAssetProcessedReceiver; M],
+	pub reclaim_amount: u64,
+	pub recipient: [u8; 32],
+	pub network_id: NetworkId,
+}
+
+impl From<TransferWitness> for MultiTransferWitness<2, 2> {
+	fn from(witness: TransferWitness) -> Self {
+		Self {
+			commit_param: witness.commit_param,
+			hash_param: witness.hash_param,
+			senders: [witness.sender_1, witness.sender_2],
+			receivers: [witness.receiver_1, witness.receiver_2],
+			fee: witness.fee,
+			network_id: witness.network_id,
+		}
+	}
+}
+
+impl From<ReclaimWitness> for MultiReclaimWitness<2, 1> {
+	fn from(witness: ReclaimWitness) -> Self {
+		Self {
+			commit_param: witness.commit_param,
+			hash_param: witness.hash_param,
+			senders: [witness.sender_1, witness.sender_2],
+			receivers: [witness.receiver],
+			reclaim_amount: witness.reclaim_amount,
+			recipient: witness.recipient,
+			network_id: witness.network_id,
+		}
+	}
+}
+
+/// Build the proof of a private transfer with the circuit `C`.
+pub fn prove_transfer<C, R>(
+	proving_key: &Groth16Pk,
+	witness: TransferWitness,
+	rng: &mut R,
+) -> Result<PrivateTransferData, MantaError>
+where
+	C: ConstraintSynthesizer<Fq> + From<TransferWitness>,
+	R: RngCore + CryptoRng,
+{
+	let mut data = transfer_data(&witness)?;
+	let proof = create_random_proof(C::from(witness), proving_key, rng)?;
+	proof.serialize(data.proof.as_mut())?;
+	Ok(data)
+}
+
+/// Build the proof of a reclaim with the circuit `C`.
+pub fn prove_reclaim<C, R>(
+	proving_key: &Groth16Pk,
+	witness: ReclaimWitness,
+	rng: &mut R,
+) -> Result<ReclaimData, MantaError>
+where
+	C: ConstraintSynthesizer<Fq> + From<ReclaimWitness>,
+	R: RngCore + CryptoRng,
+{
+	let mut data = reclaim_data(&witness)?;
+	let proof = create_random_proof(C::from(witness), proving_key, rng)?;
+	proof.serialize(data.proof.as_mut())?;
+	Ok(data)
+}
+
+/// Build the proof of a private transfer of `N` inputs and `M` outputs
+/// with the circuit `C`.
+pub fn prove_multi_transfer<C, R, const N: usize, const M: usize>(
+	proving_key: &Groth16Pk,
+	witness: MultiTransferWitness<N, M>,
+	rng: &mut R,
+) -> Result<MultiTransferData<N, M>, MantaError>
+where
+	C: ConstraintSynthesizer<Fq> + From<MultiTransferWitness<N, M>>,
+	R: RngCore + CryptoRng,
+{
+	let mut data = MultiTransferData {
+		network_id: witness.network_id,
+		asset_id: first_asset_id(&witness.senders)?,
+		fee: witness.fee,
+		..Default::default()
+	};
+	fill_entries(
+		&mut data.senders,
+		&mut data.receivers,
+		&witness.senders,
+		&witness.receivers,
+	)?;
+	let proof = create_random_proof(C::from(witness), proving_key, rng)?;
+	proof.serialize(data.proof.as_mut())?;
+	Ok(data)
+}
+
+/// Build the proof of a reclaim of `N` inputs and `M` outputs
+/// with the circuit `C`.
+pub fn prove_multi_reclaim<C, R, const N: usize, const M: usize>(
+	proving_key: &Groth16Pk,
+	witness: MultiReclaimWitness<N, M>,
+	rng: &mut R,
+) -> Result<MultiReclaimData<N, M>, MantaError>
+where
+	C: ConstraintSynthesizer<Fq> + From<MultiReclaimWitness<N, M>>,
+	R: RngCore + CryptoRng,
+{
+	let mut data = MultiReclaimData {
+		network_id: witness.network_id,
+		asset_id: first_asset_id(&witness.senders)?,
+		reclaim_amount: witness.reclaim_amount,
+		recipient: witness.recipient,
+		..Default::default()
+	};
+	fill_entries(
+		&mut data.senders,
+		&mut data.receivers,
+		&witness.senders,
+		&witness.receivers,
+	)?;
+	let proof = create_random_proof(C::from(witness), proving_key, rng)?;
+	proof.serialize(data.proof.as_mut())?;
+	Ok(data)
+}
+
+/// The asset id of a transaction, which is that of its first input.
+fn first_asset_id(senders: &[SenderMetaData]) -> Result<AssetId, MantaError> {
+	senders
+		.first()
+		.map(|sender| sender.asset.asset_id)
+		.ok_or(MantaError::SanityCheckFail)
+}
+
+/// Fill in the `SenderData` and `ReceiverData` of the entries of a witness.
+fn fill_entries(
+	sender_data: &mut [SenderData],
+	receiver_data: &mut [ReceiverData],
+	senders: &[SenderMetaData],
+	receivers: &[MantaAssetProcessedReceiver],
+) -> Result<(), MantaError> {
+	for (data, sender) in sender_data.iter_mut().zip(senders) {
+		*data = sender.sender_data()?;
+	}
+	for (data, receiver) in receiver_data.iter_mut().zip(receivers) {
+		*data = ReceiverData::from(receiver);
+	}
+	Ok(())
+}
+
+/// The data of a private transfer, without its proof.
+pub(crate) fn transfer_data(witness: &TransferWitness) -> Result<PrivateTransferData, MantaError> {
+	Ok(PrivateTransferData {
+		network_id: witness.network_id,
+		asset_id: witness.sender_1.asset.asset_id,
+		fee: witness.fee,
+		sender_1: witness.sender_1.sender_data()?,
+		sender_2: witness.sender_2.sender_data()?,
+		receiver_1: ReceiverData::from(&witness.receiver_1),
+		receiver_2: ReceiverData::from(&witness.receiver_2),
+		proof: [0u8; 192],
+	})
+}
+
+/// The data of a reclaim, without its proof.
+pub(crate) fn reclaim_data(witness: &ReclaimWitness) -> Result<ReclaimData, MantaError> {
+	Ok(ReclaimData {
+		network_id: witness.network_id,
+		asset_id: witness.sender_1.asset.asset_id,
+		reclaim_amount: witness.reclaim_amount,
+		recipient: witness.recipient,
+		sender_1: witness.sender_1.sender_data()?,
+		sender_2: witness.sender_2.sender_data()?,
+		receiver: ReceiverData::from(&witness.receiver),
+		proof: [0u8; 192],
+	})
+}
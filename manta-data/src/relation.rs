@@ -0,0 +1,218 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a native checker of the relations that the
+//! transfer and reclaim circuits enforce, to debug witnesses that do
+//! not make valid proofs. It is only available with the `prover` feature.
+//!
+//! Unlike the circuits, the checker does not stop at the first violated
+//! relation: it reports all of them.
+
+use super::*;
+use ark_crypto_primitives::prf::{Blake2s, PRF};
+use ark_std::vec::Vec;
+use manta_asset::MantaAssetProcessedReceiver;
+use manta_crypto::{Commitment, CommitmentParam, HashParam, MantaCrypto};
+
+/// A relation of the transfer and reclaim circuits.
+/// Inputs and outputs are numbered from 0, in the order of the witness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+	/// `sn = PRF(sk, rho)` for an input.
+	InputVoidNumber { input: usize },
+	/// `k = com(pk||rho, r)` with `pk = PRF(sk, 0)` for an input.
+	InputKOpening { input: usize },
+	/// `cm = com( asset_id | v||k, s )` for an input,
+	/// with the asset id of the transaction.
+	InputCommitment { input: usize },
+	/// `cm` is a leaf of the tree of `root` for an input of nonzero value.
+	InputMembership { input: usize },
+	/// `cm = com( asset_id | v||k, s )` for an output,
+	/// with the asset id of the transaction.
+	OutputCommitment { output: usize },
+	/// The values of the inputs add up to the values of the outputs,
+	/// and the fee for a transfer or the reclaimed amount for a reclaim.
+	ValueBalance,
+}
+
+impl TransferWitness {
+	/// Check the relations of the transfer circuit,
+	/// and report all the violated ones.
+	pub fn check_relations(&self) -> Result<(), Vec<Relation>> {
+		let asset_id = self.sender_1.asset.asset_id;
+		let mut violated = Vec::new();
+		for (input, sender) in [&self.sender_1, &self.sender_2].iter().enumerate() {
+			check_input(
+				(&self.commit_param, &self.hash_param),
+				input,
+				sender,
+				asset_id,
+				&mut violated,
+			);
+		}
+		for (output, receiver) in [&self.receiver_1, &self.receiver_2].iter().enumerate() {
+			check_output(
+				&self.commit_param,
+				output,
+				receiver,
+				asset_id,
+				&mut violated,
+			);
+		}
+
+		let inputs = self.sender_1.asset.priv_info.value as u128
+			+ self.sender_2.asset.priv_info.value as u128;
+		let outputs =
+			self.receiver_1.value as u128 + self.receiver_2.value as u128 + self.fee as u128;
+		if inputs != outputs {
+			violated.push(Relation::ValueBalance);
+		}
+
+		if violated.is_empty() {
+			Ok(())
+		} else {
+			Err(violated)
+		}
+	}
+}
+
+impl ReclaimWitness {
+	/// Check the relations of the reclaim circuit,
+	/// and report all the violated ones.
+	pub fn check_relations(&self) -> Result<(), Vec<Relation>> {
+		let asset_id = self.sender_1.asset.asset_id;
+		let mut violated = Vec::new();
+		for (input, sender) in [&self.sender_1, &self.sender_2].iter().enumerate() {
+			check_input(
+				(&self.commit_param, &self.hash_param),
+				input,
+				sender,
+				asset_id,
+				&mut violated,
+			);
+		}
+		check_output(
+			&self.commit_param,
+			0,
+			&self.receiver,
+			asset_id,
+			&mut violated,
+		);
+
+		let inputs = self.sender_1.asset.priv_info.value as u128
+			+ self.sender_2.asset.priv_info.value as u128;
+		let outputs = self.receiver.value as u128 + self.reclaim_amount as u128;
+		if inputs != outputs {
+			violated.push(Relation::ValueBalance);
+		}
+
+		if violated.is_empty() {
+			Ok(())
+		} else {
+			Err(violated)
+		}
+	}
+}
+
+/// Check the relations of the `input`-th input, `sender`.
+/// A relation that cannot be evaluated, e.g., because of a malformed
+/// randomness, is violated.
+fn check_input(
+	(commit_param, hash_param): (&CommitmentParam, &HashParam),
+	input: usize,
+	sender: &SenderMetaData,
+	asset_id: AssetId,
+	violated: &mut Vec<Relation>,
+) {
+	let asset = &sender.asset;
+	let sk = &asset.priv_info.sk;
+
+	// sn = PRF(sk, rho)
+	if <Blake2s as PRF>::evaluate(sk, &asset.pub_info.rho).ok() != Some(asset.void_number) {
+		violated.push(Relation::InputVoidNumber { input });
+	}
+
+	// k = com(pk||rho, r), where pk = PRF(sk, 0)
+	let k = <Blake2s as PRF>::evaluate(sk, &[0u8; 32])
+		.ok()
+		.and_then(|pk| {
+			let payload = [pk, asset.pub_info.rho].concat();
+			<MantaCrypto as Commitment>::commit(commit_param, &payload, &asset.pub_info.r).ok()
+		});
+	if k != Some(asset.pub_info.k) {
+		violated.push(Relation::InputKOpening { input });
+	}
+
+	// cm = com( asset_id | v||k, s )
+	if !opens(
+		commit_param,
+		asset_id,
+		asset.priv_info.value,
+		&asset.pub_info.k,
+		&asset.pub_info.s,
+		&asset.utxo,
+	) {
+		violated.push(Relation::InputCommitment { input });
+	}
+
+	// cm is a leaf of the tree of root, unless the asset is worthless
+	let is_member = sender
+		.membership
+		.verify(hash_param, &sender.root, &asset.utxo)
+		.unwrap_or(false);
+	if asset.priv_info.value != 0 && !is_member {
+		violated.push(Relation::InputMembership { input });
+	}
+}
+
+/// Check the relations of the `output`-th output, `receiver`.
+fn check_output(
+	commit_param: &CommitmentParam,
+	output: usize,
+	receiver: &MantaAssetProcessedReceiver,
+	asset_id: AssetId,
+	violated: &mut Vec<Relation>,
+) {
+	let address = &receiver.prepared_data;
+	if !opens(
+		commit_param,
+		asset_id,
+		receiver.value,
+		&address.k,
+		&address.s,
+		&receiver.utxo,
+	) {
+		violated.push(Relation::OutputCommitment { output });
+	}
+}
+
+/// Whether `cm = com( asset_id | v||k, s )`.
+fn opens(
+	commit_param: &CommitmentParam,
+	asset_id: AssetId,
+	value: u64,
+	k: &[u8; 32],
+	s: &[u8; 32],
+	cm: &[u8; 32],
+) -> bool {
+	let payload = [
+		(asset_id as u64).to_le_bytes().as_ref(),
+		value.to_le_bytes().as_ref(),
+		k.as_ref(),
+	]
+	.concat();
+	<MantaCrypto as Commitment>::check_commitment(commit_param, &payload, s, cm).unwrap_or(false)
+}
@@ -20,8 +20,9 @@ use manta_crypto::MantaSerDes;
 use manta_error::MantaError;
 
 impl MantaSerDes for MintData {
-	/// Serialize the mint data into an array of 104 bytes.
+	/// Serialize the mint data into an array of 116 bytes.
 	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
+		writer.write_all(&self.network_id.to_le_bytes())?;
 		writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
 		writer.write_all(self.amount.to_le_bytes().as_ref())?;
 		writer.write_all(&self.cm)?;
@@ -29,10 +30,14 @@ impl MantaSerDes for MintData {
 		writer.write_all(&self.s).map_err(|e| e.into())
 	}
 
-	/// Deserialize an array of 104 bytes into a MintData.
+	/// Deserialize an array of 116 bytes into a MintData.
 	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
 		let mut data = MintData::default();
 
+		let mut buf = [0u8; 4];
+		reader.read_exact(buf.as_mut())?;
+		data.network_id = u32::from_le_bytes(buf);
+
 		let mut buf1 = [0u8; 8];
 		let mut buf2 = [0u8; 8];
 		reader.read_exact(buf1.as_mut())?;
@@ -48,67 +53,191 @@ impl MantaSerDes for MintData {
 	}
 }
 
+impl MintData {
+	/// Serialize the mint data into the payload of `version`.
+	/// A `V0` payload is an array of 112 bytes, without the network id.
+	pub fn serialize_version<W: Write>(
+		&self,
+		version: PayloadVersion,
+		mut writer: W,
+	) -> Result<(), MantaError> {
+		match version {
+			PayloadVersion::V0 => {
+				writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
+				writer.write_all(self.amount.to_le_bytes().as_ref())?;
+				writer.write_all(&self.cm)?;
+				writer.write_all(&self.k)?;
+				writer.write_all(&self.s).map_err(|e| e.into())
+			}
+			PayloadVersion::V1 => self.serialize(writer),
+		}
+	}
+
+	/// Deserialize the payload of `version` into a mint data.
+	/// A `V0` payload has no network id, and is decoded for `network_id`;
+	/// a `V1` payload has its own network id.
+	pub fn deserialize_version<R: Read>(
+		version: PayloadVersion,
+		mut reader: R,
+		network_id: NetworkId,
+	) -> Result<Self, MantaError> {
+		match version {
+			PayloadVersion::V0 => {
+				let mut data = MintData {
+					network_id,
+					..Default::default()
+				};
+
+				let mut buf = [0u8; 8];
+				reader.read_exact(buf.as_mut())?;
+				data.asset_id = u64::from_le_bytes(buf);
+
+				reader.read_exact(buf.as_mut())?;
+				data.amount = u64::from_le_bytes(buf);
+
+				reader.read_exact(&mut data.cm)?;
+				reader.read_exact(&mut data.k)?;
+				reader.read_exact(&mut data.s)?;
+				Ok(data)
+			}
+			PayloadVersion::V1 => Self::deserialize(reader),
+		}
+	}
+}
+
 impl MantaSerDes for PrivateTransferData {
-	/// Serialize the private transfer data
-	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
-		self.sender_1.serialize(&mut writer)?;
-		self.sender_2.serialize(&mut writer)?;
-		self.receiver_1.serialize(&mut writer)?;
-		self.receiver_2.serialize(&mut writer)?;
-		writer.write_all(&self.proof.as_ref()).map_err(|e| e.into())
+	/// Serialize the private transfer data, as a `MultiTransferData<2, 2>`
+	fn serialize<W: Write>(&self, writer: W) -> Result<(), MantaError> {
+		MultiTransferData::<2, 2>::from(self.clone()).serialize(writer)
 	}
 
-	/// Deserialize the private transfer data
-	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
-		let sender_1 = SenderData::deserialize(&mut reader)?;
-		let sender_2 = SenderData::deserialize(&mut reader)?;
-		let receiver_1 = ReceiverData::deserialize(&mut reader)?;
-		let receiver_2 = ReceiverData::deserialize(&mut reader)?;
-
-		let mut proof = [0u8; 192];
-		reader.read_exact(proof.as_mut())?;
-
-		Ok(Self {
-			sender_1,
-			sender_2,
-			receiver_1,
-			receiver_2,
-			proof,
-		})
+	/// Deserialize the private transfer data, as a `MultiTransferData<2, 2>`
+	fn deserialize<R: Read>(reader: R) -> Result<Self, MantaError> {
+		MultiTransferData::<2, 2>::deserialize(reader).map(Self::from)
 	}
 }
 
-impl MantaSerDes for ReclaimData {
-	/// Serialize the private transfer data
-	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
-		writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
-		writer.write_all(self.reclaim_amount.to_le_bytes().as_ref())?;
-		self.sender_1.serialize(&mut writer)?;
-		self.sender_2.serialize(&mut writer)?;
-		self.receiver.serialize(&mut writer)?;
-		writer.write_all(&self.proof.as_ref()).map_err(|e| e.into())
+impl PrivateTransferData {
+	/// Serialize the private transfer data into the payload of `version`.
+	/// A `V0` payload is made of the senders, the receivers and the proof;
+	/// the network id and the asset id are left out, and it has no fee,
+	/// so a transfer with a fee is a failed sanity check.
+	pub fn serialize_version<W: Write>(
+		&self,
+		version: PayloadVersion,
+		mut writer: W,
+	) -> Result<(), MantaError> {
+		match version {
+			PayloadVersion::V0 => {
+				if self.fee != 0 {
+					return Err(MantaError::SanityCheckFail);
+				}
+				self.sender_1.serialize(&mut writer)?;
+				self.sender_2.serialize(&mut writer)?;
+				self.receiver_1.serialize(&mut writer)?;
+				self.receiver_2.serialize(&mut writer)?;
+				writer.write_all(&self.proof).map_err(|e| e.into())
+			}
+			PayloadVersion::V1 => self.serialize(writer),
+		}
 	}
 
-	/// Deserialize the private transfer data
-	fn deserialize<R: Read>(mut reader: R) -> Result<Self, MantaError> {
-		let mut data = ReclaimData::default();
+	/// Deserialize the payload of `version` into a private transfer data.
+	/// A `V0` payload has no network id, and is decoded for `network_id`,
+	/// with the default asset id and no fee; a `V1` payload has its own
+	/// network id.
+	pub fn deserialize_version<R: Read>(
+		version: PayloadVersion,
+		mut reader: R,
+		network_id: NetworkId,
+	) -> Result<Self, MantaError> {
+		match version {
+			PayloadVersion::V0 => {
+				let mut data = PrivateTransferData {
+					network_id,
+					..Default::default()
+				};
+				data.sender_1 = SenderData::deserialize(&mut reader)?;
+				data.sender_2 = SenderData::deserialize(&mut reader)?;
+				data.receiver_1 = ReceiverData::deserialize(&mut reader)?;
+				data.receiver_2 = ReceiverData::deserialize(&mut reader)?;
+				reader.read_exact(&mut data.proof)?;
+				Ok(data)
+			}
+			PayloadVersion::V1 => Self::deserialize(reader),
+		}
+	}
+}
 
-		let mut buf = [0u8; 8];
-		reader.read_exact(buf.as_mut())?;
-		data.asset_id = u64::from_le_bytes(buf);
+impl<AccountId: ReclaimAccount> MantaSerDes for ReclaimData<AccountId> {
+	/// Serialize the reclaim data, as a `MultiReclaimData<2, 1>`
+	fn serialize<W: Write>(&self, writer: W) -> Result<(), MantaError> {
+		MultiReclaimData::<2, 1, AccountId>::from(self.clone()).serialize(writer)
+	}
 
-		reader.read_exact(buf.as_mut())?;
-		data.reclaim_amount = u64::from_le_bytes(buf);
+	/// Deserialize the reclaim data, as a `MultiReclaimData<2, 1>`
+	fn deserialize<R: Read>(reader: R) -> Result<Self, MantaError> {
+		MultiReclaimData::<2, 1, AccountId>::deserialize(reader).map(Self::from)
+	}
+}
+
+impl<AccountId: ReclaimAccount> ReclaimData<AccountId> {
+	/// Serialize the reclaim data into the payload of `version`.
+	/// A `V0` payload is made of the asset id, the reclaimed amount, the
+	/// senders, the receiver and the proof; the network id is left out,
+	/// and it has no recipient, so a reclaim whose recipient does not
+	/// encode to zeros is a failed sanity check.
+	pub fn serialize_version<W: Write>(
+		&self,
+		version: PayloadVersion,
+		mut writer: W,
+	) -> Result<(), MantaError> {
+		match version {
+			PayloadVersion::V0 => {
+				if self.recipient.encode_recipient() != [0u8; 32] {
+					return Err(MantaError::SanityCheckFail);
+				}
+				writer.write_all(&(self.asset_id as u64).to_le_bytes())?;
+				writer.write_all(self.reclaim_amount.to_le_bytes().as_ref())?;
+				self.sender_1.serialize(&mut writer)?;
+				self.sender_2.serialize(&mut writer)?;
+				self.receiver.serialize(&mut writer)?;
+				writer.write_all(&self.proof).map_err(|e| e.into())
+			}
+			PayloadVersion::V1 => self.serialize(writer),
+		}
+	}
 
-		data.sender_1 = SenderData::deserialize(&mut reader)?;
-		data.sender_2 = SenderData::deserialize(&mut reader)?;
-		data.receiver = ReceiverData::deserialize(&mut reader)?;
+	/// Deserialize the payload of `version` into a reclaim data.
+	/// A `V0` payload has no network id, and is decoded for `network_id`,
+	/// with the recipient of zeros; a `V1` payload has its own network id.
+	pub fn deserialize_version<R: Read>(
+		version: PayloadVersion,
+		mut reader: R,
+		network_id: NetworkId,
+	) -> Result<Self, MantaError> {
+		match version {
+			PayloadVersion::V0 => {
+				let mut data = ReclaimData {
+					network_id,
+					..Default::default()
+				};
 
-		let mut buf = [0u8; 192];
-		reader.read_exact(&mut buf)?;
-		data.proof.copy_from_slice(buf.as_ref());
+				let mut buf = [0u8; 8];
+				reader.read_exact(buf.as_mut())?;
+				data.asset_id = u64::from_le_bytes(buf);
 
-		Ok(data)
+				reader.read_exact(buf.as_mut())?;
+				data.reclaim_amount = u64::from_le_bytes(buf);
+
+				data.sender_1 = SenderData::deserialize(&mut reader)?;
+				data.sender_2 = SenderData::deserialize(&mut reader)?;
+				data.receiver = ReceiverData::deserialize(&mut reader)?;
+				reader.read_exact(&mut data.proof)?;
+				Ok(data)
+			}
+			PayloadVersion::V1 => Self::deserialize(reader),
+		}
 	}
 }
 
@@ -0,0 +1,1368 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::Fq;
+use ark_ff::Zero;
+use ark_groth16::{create_random_proof, generate_random_parameters, ProvingKey};
+use ark_relations::{
+	lc,
+	r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_serialize::CanonicalSerialize;
+use ark_std::{
+	boxed::Box,
+	rand::{CryptoRng, RngCore},
+	vec::Vec,
+};
+use manta_asset::{Sampling, SanityCheck, TEST_ASSET, TEST_NETWORK};
+use manta_crypto::{
+	CommitmentParam, Groth16Vk, HashParam, MantaCrypto, MantaSerDes, MantaZKPVerifier, MerkleTree,
+	VerificationKey, COMMIT_PARAM, HASH_PARAM, RECLAIM_PK, TRANSFER_PK,
+};
+use manta_ledger::{LedgerError, LedgerSharding, MantaPrivateAssetLedger};
+
+// a circuit that only exposes its public inputs, so that the verifiers
+// can be tested against real proofs for any payload
+#[derive(Clone)]
+struct PublicInputCircuit(Vec<Fq>);
+
+impl ConstraintSynthesizer<Fq> for PublicInputCircuit {
+	fn generate_constraints(self, cs: ConstraintSystemRef<Fq>) -> Result<(), SynthesisError> {
+		for input in self.0 {
+			let var = cs.new_input_variable(|| Ok(input))?;
+			cs.enforce_constraint(lc!() + var, lc!() + Variable::One, lc!() + var)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "prover")]
+impl From<TransferWitness> for PublicInputCircuit {
+	fn from(witness: TransferWitness) -> Self {
+		let data = crate::prover::transfer_data(&witness).unwrap();
+		Self(data.public_inputs().unwrap())
+	}
+}
+
+#[cfg(feature = "prover")]
+impl From<ReclaimWitness> for PublicInputCircuit {
+	fn from(witness: ReclaimWitness) -> Self {
+		let data = crate::prover::reclaim_data(&witness).unwrap();
+		Self(data.public_inputs().unwrap())
+	}
+}
+
+// the proving key of a `PublicInputCircuit` with `len` public inputs
+fn public_input_key<R: RngCore + CryptoRng>(len: usize, rng: &mut R) -> ProvingKey<Bls12_381> {
+	let circuit = PublicInputCircuit(ark_std::vec![Fq::zero(); len]);
+	generate_random_parameters::<Bls12_381, _, _>(circuit, rng).unwrap()
+}
+
+// a proof for the public inputs `inputs`
+fn public_input_proof<R: RngCore + CryptoRng>(
+	pk: &ProvingKey<Bls12_381>,
+	inputs: Vec<Fq>,
+	rng: &mut R,
+) -> [u8; 192] {
+	let proof = create_random_proof(PublicInputCircuit(inputs), pk, rng).unwrap();
+	let mut buf = [0u8; 192];
+	proof.serialize(buf.as_mut()).unwrap();
+	buf
+}
+
+// a verification key in the published, uncompressed, encoding
+fn verification_key(vk: &Groth16Vk) -> VerificationKey {
+	let mut buf = Vec::new();
+	vk.serialize_uncompressed(&mut buf).unwrap();
+	VerificationKey {
+		data: Box::leak(buf.into_boxed_slice()),
+	}
+}
+
+// a random asset of `value`
+fn random_asset<R: RngCore + CryptoRng>(
+	commit_param: &CommitmentParam,
+	value: u64,
+	rng: &mut R,
+) -> MantaAsset {
+	let mut sk = [0u8; 32];
+	rng.fill_bytes(&mut sk);
+	MantaAsset::sample(commit_param, &sk, &TEST_ASSET, &value, rng).unwrap()
+}
+
+// a private transfer with well-formed fields, and a dummy proof
+fn random_transfer_data<R: RngCore + CryptoRng>(rng: &mut R) -> PrivateTransferData {
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let assets: Vec<MantaAsset> = (0..4)
+		.map(|_| random_asset(&commit_param, 10, rng))
+		.collect();
+	let root =
+		<MantaCrypto as MerkleTree>::root(hash_param, &[assets[0].utxo, assets[1].utxo]).unwrap();
+
+	let sender = |asset: &MantaAsset| SenderData {
+		k: asset.pub_info.k,
+		void_number: asset.void_number,
+		root,
+	};
+	let receiver = |asset: &MantaAsset| ReceiverData {
+		k: asset.pub_info.k,
+		cm: asset.utxo,
+		..Default::default()
+	};
+	PrivateTransferData {
+		network_id: TEST_NETWORK,
+		asset_id: TEST_ASSET,
+		fee: 0,
+		sender_1: sender(&assets[0]),
+		sender_2: sender(&assets[1]),
+		receiver_1: receiver(&assets[2]),
+		receiver_2: receiver(&assets[3]),
+		proof: [0u8; 192],
+	}
+}
+
+// a random commitment that is assigned to `shard`
+fn random_commitment<R: RngCore>(shard: u8, rng: &mut R) -> [u8; 32] {
+	let mut cm = [0u8; 32];
+	rng.fill_bytes(&mut cm);
+	cm[0] = shard;
+	cm
+}
+
+#[test]
+fn test_incremental_witness() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut sk = [0u8; 32];
+	rng.fill_bytes(&mut sk);
+	let asset = MantaAsset::sample(&commit_param, &sk, &TEST_ASSET, &100, &mut rng).unwrap();
+	let shard = asset.utxo[0];
+
+	let mut ledger = MantaPrivateAssetLedger::default();
+	for _ in 0..3 {
+		let cm = random_commitment(shard, &mut rng);
+		ledger.update(&cm, hash_param.clone()).unwrap();
+	}
+	ledger.update(&asset.utxo, hash_param.clone()).unwrap();
+	let mut witness = IncrementalWitness::from_ledger(&hash_param, &asset, &ledger).unwrap();
+
+	let mut checkpoint = ledger.checkpoint();
+	for _ in 0..5 {
+		let cm = random_commitment(shard, &mut rng);
+		ledger.update(&cm, hash_param.clone()).unwrap();
+		let delta = ledger.delta_since(&checkpoint).unwrap();
+		witness.apply_delta(&hash_param, &delta).unwrap();
+		checkpoint.apply(&delta).unwrap();
+
+		// the witness follows the ledger root and a freshly built membership
+		let metadata = asset
+			.build(&hash_param, &ledger.current_tree(shard).to_vec())
+			.unwrap();
+		let root = witness.root(&hash_param).unwrap();
+		let mut root_bytes = [0u8; 32];
+		root.serialize(root_bytes.as_mut()).unwrap();
+		assert_eq!(&root_bytes, ledger.shard(shard).root());
+		assert_eq!(root, metadata.root);
+		assert!(witness.verify(&hash_param).unwrap());
+
+		// and yields the same metadata, without replaying the tree
+		let tracked = witness.sender_metadata(&hash_param).unwrap();
+		assert_eq!(tracked.asset, metadata.asset);
+		assert_eq!(tracked.root, metadata.root);
+		assert_eq!(tracked.membership, metadata.membership);
+		assert!(tracked
+			.membership
+			.verify(&hash_param, &tracked.root, &asset.utxo)
+			.unwrap());
+		assert!(!tracked
+			.membership
+			.verify(
+				&hash_param,
+				&tracked.root,
+				&random_commitment(shard, &mut rng)
+			)
+			.unwrap());
+	}
+
+	// a path has one pair of nodes per level of the tree
+	assert!(MembershipPath::new(Vec::new()).is_err());
+	let tracked = witness.sender_metadata(&hash_param).unwrap();
+	assert_eq!(
+		MembershipPath::new(tracked.membership.path().to_vec()).unwrap(),
+		tracked.membership
+	);
+}
+
+#[test]
+fn test_public_input_layout() {
+	let mut rng = ark_std::test_rng();
+	let transfer = random_transfer_data(&mut rng);
+	assert_eq!(
+		transfer.public_inputs().unwrap().len(),
+		input_len(PRIVATE_TRANSFER_INPUT_LAYOUT)
+	);
+
+	let reclaim = ReclaimData {
+		network_id: TEST_NETWORK,
+		asset_id: TEST_ASSET,
+		reclaim_amount: 10,
+		recipient: [7u8; 32],
+		sender_1: transfer.sender_1,
+		sender_2: transfer.sender_2,
+		receiver: transfer.receiver_1,
+		proof: [0u8; 192],
+	};
+	assert_eq!(
+		reclaim.public_inputs().unwrap().len(),
+		input_len(RECLAIM_INPUT_LAYOUT)
+	);
+
+	// the recipient is part of the payload, and of the public inputs
+	let mut buf = Vec::new();
+	reclaim.serialize(&mut buf).unwrap();
+	assert_eq!(buf.len(), RECLAIM_PAYLOAD_SIZE);
+	let recovered: ReclaimData = MantaSerDes::deserialize(buf.as_slice()).unwrap();
+	assert_eq!(recovered, reclaim);
+	let multi = MultiReclaimData::<2, 1>::from(reclaim.clone());
+	assert_eq!(multi.public_inputs(), reclaim.public_inputs());
+	let mut other = reclaim.clone();
+	other.recipient = [8u8; 32];
+	assert_ne!(other.public_inputs(), reclaim.public_inputs());
+}
+
+#[test]
+fn test_verification_error_names_field() {
+	let mut rng = ark_std::test_rng();
+	let mut transfer = random_transfer_data(&mut rng);
+	transfer.sender_2.root = [0xffu8; 32];
+	assert_eq!(
+		transfer.public_inputs(),
+		Err(VerificationError::MalformedField("sender_2.root"))
+	);
+	transfer.sender_2.root = transfer.sender_1.root;
+	transfer.receiver_2.cm = [0xffu8; 32];
+	assert_eq!(
+		transfer.public_inputs(),
+		Err(VerificationError::MalformedField("receiver_2.cm"))
+	);
+
+	let mut reclaim = ReclaimData {
+		network_id: TEST_NETWORK,
+		asset_id: TEST_ASSET,
+		reclaim_amount: 10,
+		recipient: [7u8; 32],
+		sender_1: transfer.sender_1,
+		sender_2: transfer.sender_1,
+		receiver: transfer.receiver_2,
+		proof: [0u8; 192],
+	};
+	assert_eq!(
+		reclaim.public_inputs(),
+		Err(VerificationError::MalformedField("receiver.cm"))
+	);
+	reclaim.receiver = transfer.receiver_1;
+	reclaim.sender_1.k = [0xffu8; 32];
+	assert_eq!(
+		reclaim.public_inputs(),
+		Err(VerificationError::MalformedField("sender_1.k"))
+	);
+}
+
+#[test]
+fn test_generated_input_layout() {
+	// the generated layouts of the 2-in arities are the named ones
+	let same_place = |generated: &[InputSegment], named: &[InputSegment]| {
+		generated.len() == named.len()
+			&& generated
+				.iter()
+				.zip(named)
+				.all(|(g, n)| g.encoding == n.encoding && g.len == n.len)
+	};
+	let transfer = transfer_input_layout(MultiTransferData::<2, 2>::ARITY);
+	assert!(same_place(&transfer, PRIVATE_TRANSFER_INPUT_LAYOUT));
+	let reclaim = reclaim_input_layout(MultiReclaimData::<2, 1>::ARITY);
+	assert!(same_place(&reclaim, RECLAIM_INPUT_LAYOUT));
+	assert_eq!(transfer[3].field, "receivers.cm");
+	assert_eq!(transfer[3].index, Some(1));
+	assert_eq!(PRIVATE_TRANSFER_INPUT_LAYOUT[3].field, "receiver_2.cm");
+
+	// and those of the other arities have the length of their inputs
+	assert_eq!(
+		input_len(&transfer_input_layout(BatchTransferData::ARITY)),
+		BatchTransferData::PUBLIC_INPUT_LEN
+	);
+	assert_eq!(
+		input_len(&reclaim_input_layout(MultiReclaimData::<4, 2>::ARITY)),
+		MultiReclaimData::<4, 2>::PUBLIC_INPUT_LEN
+	);
+}
+
+// an account of a runtime, with its own type
+#[derive(Debug, Clone, PartialEq)]
+struct Account([u8; 32]);
+
+impl ReclaimAccount for Account {
+	fn encode_recipient(&self) -> [u8; 32] {
+		self.0
+	}
+
+	fn decode_recipient(bytes: [u8; 32]) -> Self {
+		Self(bytes)
+	}
+}
+
+#[test]
+fn test_reclaim_account_type() {
+	let mut rng = ark_std::test_rng();
+	let transfer = random_transfer_data(&mut rng);
+	let reclaim = ReclaimData {
+		network_id: TEST_NETWORK,
+		asset_id: TEST_ASSET,
+		reclaim_amount: 10,
+		recipient: Account([7u8; 32]),
+		sender_1: transfer.sender_1,
+		sender_2: transfer.sender_2,
+		receiver: transfer.receiver_1,
+		proof: [0u8; 192],
+	};
+
+	// the account type carries over to reclaims of any arity
+	let multi = MultiReclaimData::<2, 1, Account>::from(reclaim.clone());
+	assert_eq!(multi.recipient, reclaim.recipient);
+	assert_eq!(multi.public_inputs(), reclaim.public_inputs());
+	let mut buf = Vec::new();
+	reclaim.serialize(&mut buf).unwrap();
+	assert_eq!(buf.len(), RECLAIM_PAYLOAD_SIZE);
+	let recovered: MultiReclaimData<2, 1, Account> =
+		MantaSerDes::deserialize(buf.as_slice()).unwrap();
+	assert_eq!(recovered, multi);
+	assert_eq!(ReclaimData::from(recovered), reclaim);
+}
+
+#[test]
+fn test_try_verify_reports_step() {
+	let mut rng = ark_std::test_rng();
+	let mut transfer = random_transfer_data(&mut rng);
+	let inputs = transfer.public_inputs().unwrap();
+	let pk = public_input_key(inputs.len(), &mut rng);
+	let key = verification_key(&pk.vk);
+	transfer.proof = public_input_proof(&pk, inputs, &mut rng);
+	assert_eq!(transfer.try_verify(&key), Ok(()));
+	assert!(transfer.verify(&key));
+
+	let garbage = VerificationKey { data: &[0xffu8; 8] };
+	assert_eq!(
+		transfer.try_verify(&garbage),
+		Err(VerificationError::InvalidVerificationKey)
+	);
+
+	let mut broken = transfer.clone();
+	broken.proof = [0xffu8; 192];
+	assert_eq!(
+		broken.try_verify(&key),
+		Err(VerificationError::InvalidProof)
+	);
+
+	let mut broken = transfer.clone();
+	broken.sender_2.root = [0xffu8; 32];
+	assert_eq!(
+		broken.try_verify(&key),
+		Err(VerificationError::MalformedField("sender_2.root"))
+	);
+
+	// a well-formed payload that the proof is not for
+	let mut broken = transfer.clone();
+	broken.fee += 1;
+	assert_eq!(
+		broken.try_verify(&key),
+		Err(VerificationError::ProofRejected)
+	);
+	assert!(!broken.verify(&key));
+}
+
+#[test]
+fn test_payload_versions() {
+	let mut rng = ark_std::test_rng();
+	let mut transfer = random_transfer_data(&mut rng);
+	transfer.network_id = TEST_NETWORK + 1;
+	transfer.asset_id = TEST_ASSET + 1;
+
+	// a `V0` payload is in the format of the published keys,
+	// without the network id and the asset id
+	let v0 = PayloadVersion::V0;
+	let mut buf = Vec::new();
+	transfer.serialize_version(v0, &mut buf).unwrap();
+	assert_eq!(buf.len(), 608);
+	assert_eq!(buf.len(), v0.private_transfer_payload_size());
+	let legacy =
+		PrivateTransferData::deserialize_version(v0, buf.as_slice(), TEST_NETWORK).unwrap();
+	assert_eq!(
+		legacy,
+		PrivateTransferData {
+			network_id: TEST_NETWORK,
+			asset_id: AssetId::default(),
+			..transfer.clone()
+		}
+	);
+	let mut buf = Vec::new();
+	transfer
+		.serialize_version(PayloadVersion::CURRENT, &mut buf)
+		.unwrap();
+	assert_eq!(buf.len(), PRIVATE_TRANSFER_PAYLOAD_SIZE);
+	assert_eq!(
+		PrivateTransferData::deserialize_version(PayloadVersion::V1, buf.as_slice(), TEST_NETWORK)
+			.unwrap(),
+		transfer
+	);
+
+	// the published key only has the public inputs of `V0`
+	assert_eq!(
+		transfer.try_verify(&TRANSFER_PK),
+		Err(VerificationError::KeyMismatch {
+			expected: input_len(PRIVATE_TRANSFER_INPUT_LAYOUT),
+			found: input_len(PRIVATE_TRANSFER_INPUT_LAYOUT_V0),
+		})
+	);
+	assert!(!matches!(
+		transfer.try_verify_version(&TRANSFER_PK, v0),
+		Err(VerificationError::KeyMismatch { .. })
+	));
+	assert_eq!(
+		v0.published_transfer_key().map(|key| key.data),
+		Some(TRANSFER_PK.data)
+	);
+
+	// and a key of `V0` verifies the transfer as its `V0` payload
+	let inputs = transfer.public_inputs_of(v0).unwrap();
+	assert_eq!(inputs.len(), input_len(v0.private_transfer_input_layout()));
+	let pk = public_input_key(inputs.len(), &mut rng);
+	transfer.proof = public_input_proof(&pk, inputs, &mut rng);
+	let key = verification_key(&pk.vk);
+	assert_eq!(transfer.try_verify_version(&key, v0), Ok(()));
+	assert!(transfer.try_verify(&key).is_err());
+	let verifier = PreparedVerifier::new_version(&key, v0).unwrap();
+	let legacy = PrivateTransferData {
+		proof: transfer.proof,
+		..legacy
+	};
+	assert_eq!(verifier.verify_transfer(&legacy), Ok(()));
+
+	// a transfer of `V0` has no fee
+	let paying = PrivateTransferData { fee: 1, ..legacy };
+	assert_eq!(
+		paying.public_inputs_of(v0),
+		Err(VerificationError::UnboundField("fee"))
+	);
+	assert_eq!(
+		verifier.verify_transfer(&paying),
+		Err(VerificationError::UnboundField("fee"))
+	);
+	assert!(paying.serialize_version(v0, Vec::<u8>::new()).is_err());
+	assert!(paying
+		.serialize_version(PayloadVersion::V1, Vec::<u8>::new())
+		.is_ok());
+}
+
+#[test]
+fn test_mint_payload_versions() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let asset = random_asset(&commit_param, 10, &mut rng);
+	let mint = MintData {
+		network_id: TEST_NETWORK + 1,
+		asset_id: asset.asset_id,
+		amount: asset.priv_info.value,
+		cm: asset.utxo,
+		k: asset.pub_info.k,
+		s: asset.pub_info.s,
+	};
+
+	// a `V0` payload has no network id, and is decoded for a given one
+	let v0 = PayloadVersion::V0;
+	let mut buf = Vec::new();
+	mint.serialize_version(v0, &mut buf).unwrap();
+	assert_eq!(buf.len(), 112);
+	assert_eq!(buf.len(), v0.mint_payload_size());
+	let legacy = MintData::deserialize_version(v0, buf.as_slice(), TEST_NETWORK + 1).unwrap();
+	assert_eq!(legacy, mint);
+	assert!(legacy.sanity(&commit_param).unwrap());
+
+	let mut buf = Vec::new();
+	mint.serialize_version(PayloadVersion::CURRENT, &mut buf)
+		.unwrap();
+	assert_eq!(buf.len(), MINT_PAYLOAD_SIZE);
+	assert_eq!(
+		MintData::deserialize_version(PayloadVersion::V1, buf.as_slice(), TEST_NETWORK).unwrap(),
+		mint
+	);
+}
+
+#[test]
+fn test_reclaim_payload_versions() {
+	let mut rng = ark_std::test_rng();
+	let transfer = random_transfer_data(&mut rng);
+	let reclaim = ReclaimData {
+		network_id: TEST_NETWORK + 1,
+		asset_id: TEST_ASSET,
+		reclaim_amount: 10,
+		recipient: Account([0u8; 32]),
+		sender_1: transfer.sender_1,
+		sender_2: transfer.sender_2,
+		receiver: transfer.receiver_1,
+		proof: [0u8; 192],
+	};
+
+	// a `V0` payload is in the format of the published keys,
+	// without the network id and the recipient
+	let v0 = PayloadVersion::V0;
+	let mut buf = Vec::new();
+	reclaim.serialize_version(v0, &mut buf).unwrap();
+	assert_eq!(buf.len(), 512);
+	assert_eq!(buf.len(), v0.reclaim_payload_size());
+	let legacy =
+		ReclaimData::<Account>::deserialize_version(v0, buf.as_slice(), TEST_NETWORK).unwrap();
+	assert_eq!(
+		legacy,
+		ReclaimData {
+			network_id: TEST_NETWORK,
+			..reclaim.clone()
+		}
+	);
+	let mut buf = Vec::new();
+	reclaim
+		.serialize_version(PayloadVersion::CURRENT, &mut buf)
+		.unwrap();
+	assert_eq!(buf.len(), RECLAIM_PAYLOAD_SIZE);
+
+	// the published key only has the public inputs of `V0`
+	assert_eq!(
+		reclaim.try_verify(&RECLAIM_PK),
+		Err(VerificationError::KeyMismatch {
+			expected: input_len(RECLAIM_INPUT_LAYOUT),
+			found: input_len(RECLAIM_INPUT_LAYOUT_V0),
+		})
+	);
+	assert_eq!(
+		v0.published_reclaim_key().map(|key| key.data),
+		Some(RECLAIM_PK.data)
+	);
+
+	// and a key of `V0` verifies the reclaim as its `V0` payload
+	let mut reclaim = reclaim;
+	let inputs = reclaim.public_inputs_of(v0).unwrap();
+	assert_eq!(inputs.len(), input_len(v0.reclaim_input_layout()));
+	let pk = public_input_key(inputs.len(), &mut rng);
+	reclaim.proof = public_input_proof(&pk, inputs, &mut rng);
+	let key = verification_key(&pk.vk);
+	assert_eq!(reclaim.try_verify_version(&key, v0), Ok(()));
+	assert!(reclaim.try_verify(&key).is_err());
+
+	// a reclaim of `V0` has no recipient
+	let bound = ReclaimData {
+		recipient: Account([7u8; 32]),
+		..reclaim
+	};
+	assert_eq!(
+		bound.try_verify_version(&key, v0),
+		Err(VerificationError::UnboundField("recipient"))
+	);
+	assert!(bound.serialize_version(v0, Vec::<u8>::new()).is_err());
+}
+
+#[test]
+fn test_prepared_verifier() {
+	// the published keys are accepted, the transfer key as a key of `V0`
+	let verifier = PreparedVerifier::new_version(&TRANSFER_PK, PayloadVersion::V0).unwrap();
+	assert_eq!(verifier.version(), PayloadVersion::V0);
+	let verifier = PreparedVerifier::new_version(&RECLAIM_PK, PayloadVersion::V0).unwrap();
+	assert_eq!(verifier.version(), PayloadVersion::V0);
+	let garbage = VerificationKey { data: &[0xffu8; 8] };
+	assert_eq!(
+		PreparedVerifier::new(&garbage).err(),
+		Some(VerificationError::InvalidVerificationKey)
+	);
+
+	let mut rng = ark_std::test_rng();
+	let mut transfer = random_transfer_data(&mut rng);
+	let inputs = transfer.public_inputs().unwrap();
+	let pk = public_input_key(inputs.len(), &mut rng);
+	transfer.proof = public_input_proof(&pk, inputs, &mut rng);
+	let verifier = PreparedVerifier::new(&verification_key(&pk.vk)).unwrap();
+	assert_eq!(verifier.verify_transfer(&transfer), Ok(()));
+	transfer.fee += 1;
+	assert_eq!(
+		verifier.verify_transfer(&transfer),
+		Err(VerificationError::ProofRejected)
+	);
+}
+
+#[test]
+fn test_batch_verify() {
+	let mut rng = ark_std::test_rng();
+	let pk = public_input_key(input_len(PRIVATE_TRANSFER_INPUT_LAYOUT), &mut rng);
+	let mut transfers: Vec<PrivateTransferData> =
+		(0..3).map(|_| random_transfer_data(&mut rng)).collect();
+	for transfer in transfers.iter_mut() {
+		let inputs = transfer.public_inputs().unwrap();
+		transfer.proof = public_input_proof(&pk, inputs, &mut rng);
+	}
+	let verifier = PreparedVerifier::from_vk(pk.vk.clone());
+	assert_eq!(
+		verifier.batch_verify_transfers(&transfers, &mut rng),
+		Ok(())
+	);
+	assert_eq!(verifier.batch_verify_transfers(&[], &mut rng), Ok(()));
+	assert_eq!(
+		verifier.batch_verify_transfers(&transfers[..1], &mut rng),
+		Ok(())
+	);
+
+	// a forged input, or a proof for another payload, fails the batch,
+	// and the failing transfers are reported
+	let mut forged = transfers.clone();
+	forged[1].fee += 1;
+	forged[2].proof = transfers[0].proof;
+	assert_eq!(
+		verifier.batch_verify_transfers(&forged, &mut rng),
+		Err(ark_std::vec![
+			(1, VerificationError::ProofRejected),
+			(2, VerificationError::ProofRejected)
+		])
+	);
+	assert_eq!(
+		verifier.batch_verify_transfers(&forged[2..], &mut rng),
+		Err(ark_std::vec![(0, VerificationError::ProofRejected)])
+	);
+	forged[0].proof = [0xffu8; 192];
+	forged[1].fee -= 1;
+	assert_eq!(
+		verifier.batch_verify_transfers(&forged, &mut rng),
+		Err(ark_std::vec![
+			(0, VerificationError::InvalidProof),
+			(2, VerificationError::ProofRejected)
+		])
+	);
+
+	let pk = public_input_key(input_len(RECLAIM_INPUT_LAYOUT), &mut rng);
+	let mut reclaims: Vec<ReclaimData> = transfers
+		.iter()
+		.map(|transfer| ReclaimData {
+			network_id: TEST_NETWORK,
+			asset_id: TEST_ASSET,
+			reclaim_amount: 10,
+			recipient: [7u8; 32],
+			sender_1: transfer.sender_1,
+			sender_2: transfer.sender_2,
+			receiver: transfer.receiver_1,
+			proof: [0u8; 192],
+		})
+		.collect();
+	for reclaim in reclaims.iter_mut() {
+		let inputs = reclaim.public_inputs().unwrap();
+		reclaim.proof = public_input_proof(&pk, inputs, &mut rng);
+	}
+	let verifier = PreparedVerifier::from_vk(pk.vk.clone());
+	assert_eq!(verifier.batch_verify_reclaims(&reclaims, &mut rng), Ok(()));
+	reclaims[1].recipient = [8u8; 32];
+	assert_eq!(
+		verifier.batch_verify_reclaims(&reclaims, &mut rng),
+		Err(ark_std::vec![(1, VerificationError::ProofRejected)])
+	);
+}
+
+// a receiver of `value`
+#[cfg(feature = "prover")]
+fn random_receiver<R: RngCore + CryptoRng>(
+	commit_param: &CommitmentParam,
+	value: u64,
+	rng: &mut R,
+) -> manta_asset::MantaAssetProcessedReceiver {
+	use manta_asset::{CommitmentVersion, MantaAssetFullReceiver};
+
+	let mut sk = [0u8; 32];
+	rng.fill_bytes(&mut sk);
+	MantaAssetFullReceiver::sample_for_network(commit_param, &sk, &TEST_ASSET, &TEST_NETWORK, rng)
+		.unwrap()
+		.shielded_address
+		.process_with_version(&value, CommitmentVersion::V1, rng)
+		.unwrap()
+}
+
+// a transfer witness that spends assets of `values[0..2]`, posted to a
+// fresh ledger, to receivers of `values[2..4]`
+#[cfg(feature = "prover")]
+fn random_transfer_witness<R: RngCore + CryptoRng>(
+	values: [u64; 4],
+	rng: &mut R,
+) -> TransferWitness {
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut ledger = MantaPrivateAssetLedger::default();
+	let assets = [
+		random_asset(&commit_param, values[0], rng),
+		random_asset(&commit_param, values[1], rng),
+	];
+	for asset in assets.iter() {
+		ledger.update(&asset.utxo, hash_param.clone()).unwrap();
+	}
+	let sender = |asset: &MantaAsset| {
+		asset
+			.build(&hash_param, &ledger.current_tree(asset.utxo[0]).to_vec())
+			.unwrap()
+	};
+
+	TransferWitness {
+		sender_1: sender(&assets[0]),
+		sender_2: sender(&assets[1]),
+		receiver_1: random_receiver(&commit_param, values[2], rng),
+		receiver_2: random_receiver(&commit_param, values[3], rng),
+		commit_param,
+		hash_param,
+		fee: 0,
+		network_id: TEST_NETWORK,
+	}
+}
+
+#[cfg(feature = "prover")]
+#[test]
+fn test_prove_transfer_and_reclaim() {
+	let mut rng = ark_std::test_rng();
+	let witness = random_transfer_witness([10, 20, 5, 25], &mut rng);
+	let pk = public_input_key(input_len(PRIVATE_TRANSFER_INPUT_LAYOUT), &mut rng);
+	let data = prove_transfer::<PublicInputCircuit, _>(&pk, witness.clone(), &mut rng).unwrap();
+
+	// the payload is filled in from the witness, and carries a proof
+	// for its public inputs
+	assert_eq!(data.network_id, witness.network_id);
+	assert_eq!(data.asset_id, witness.sender_1.asset.asset_id);
+	assert_eq!(data.fee, witness.fee);
+	assert_eq!(data.sender_1, witness.sender_1.sender_data().unwrap());
+	assert_eq!(data.sender_2, witness.sender_2.sender_data().unwrap());
+	assert_eq!(data.receiver_1, ReceiverData::from(&witness.receiver_1));
+	assert_eq!(data.receiver_2, ReceiverData::from(&witness.receiver_2));
+	assert_eq!(data.try_verify(&verification_key(&pk.vk)), Ok(()));
+
+	let witness = ReclaimWitness {
+		commit_param: witness.commit_param,
+		hash_param: witness.hash_param,
+		sender_1: witness.sender_1,
+		sender_2: witness.sender_2,
+		receiver: witness.receiver_1,
+		reclaim_amount: 25,
+		recipient: [7u8; 32],
+		network_id: witness.network_id,
+	};
+	let pk = public_input_key(input_len(RECLAIM_INPUT_LAYOUT), &mut rng);
+	let data = prove_reclaim::<PublicInputCircuit, _>(&pk, witness.clone(), &mut rng).unwrap();
+	assert_eq!(data.reclaim_amount, witness.reclaim_amount);
+	assert_eq!(data.recipient, witness.recipient);
+	assert_eq!(data.receiver, ReceiverData::from(&witness.receiver));
+	assert_eq!(data.try_verify(&verification_key(&pk.vk)), Ok(()));
+}
+
+#[cfg(feature = "mock-proofs")]
+#[test]
+fn test_mock_proofs() {
+	let mut rng = ark_std::test_rng();
+	let witness = random_transfer_witness([10, 20, 5, 25], &mut rng);
+
+	// marker proofs are only accepted under the mock key
+	let data = mock_prove_transfer(witness.clone()).unwrap();
+	assert!(data.verify(&MOCK_VERIFICATION_KEY));
+	assert_eq!(data.try_verify(&MOCK_VERIFICATION_KEY), Ok(()));
+	assert!(!data.verify(&TRANSFER_PK));
+
+	// the marker proof binds the public inputs
+	let mut forged = data.clone();
+	forged.receiver_1.cm = data.receiver_2.cm;
+	assert_eq!(
+		forged.try_verify(&MOCK_VERIFICATION_KEY),
+		Err(VerificationError::ProofRejected)
+	);
+
+	// every verifier recognises the mock key
+	let multi = MultiTransferData::<2, 2>::from(data.clone());
+	assert_eq!(multi.try_verify(&MOCK_VERIFICATION_KEY), Ok(()));
+	let mut keys = VerificationKeys::default();
+	keys.insert_transfer(MultiTransferData::<2, 2>::ARITY, MOCK_VERIFICATION_KEY)
+		.unwrap();
+	assert_eq!(multi.verify_with(&keys), Ok(()));
+	let verifier = PreparedVerifier::new(&MOCK_VERIFICATION_KEY).unwrap();
+	assert_eq!(verifier.verify_transfer(&data), Ok(()));
+	assert_eq!(
+		verifier.batch_verify_transfers(&[data.clone(), forged, data.clone()], &mut rng),
+		Err(ark_std::vec![(1, VerificationError::ProofRejected)])
+	);
+
+	// and is only issued for witnesses that satisfy the relations
+	let mut unbalanced = witness.clone();
+	unbalanced.receiver_2 = random_receiver(&witness.commit_param, 26, &mut rng);
+	match mock_prove_transfer(unbalanced) {
+		Err(BuildError::Relations(violated)) => {
+			assert_eq!(violated, ark_std::vec![Relation::ValueBalance])
+		}
+		_ => panic!("an unbalanced transfer is not proven"),
+	}
+}
+
+#[cfg(feature = "prover")]
+#[test]
+fn test_check_relations() {
+	let mut rng = ark_std::test_rng();
+	let witness = random_transfer_witness([10, 20, 5, 25], &mut rng);
+	assert_eq!(witness.check_relations(), Ok(()));
+
+	// every violated relation is reported
+	let mut broken = witness.clone();
+	broken.sender_1.asset.void_number = [0u8; 32];
+	broken.sender_2.root = Default::default();
+	broken.receiver_1.value = 6;
+	assert_eq!(
+		broken.check_relations(),
+		Err(ark_std::vec![
+			Relation::InputVoidNumber { input: 0 },
+			Relation::InputMembership { input: 1 },
+			Relation::OutputCommitment { output: 0 },
+			Relation::ValueBalance,
+		])
+	);
+}
+
+#[cfg(feature = "prover")]
+#[test]
+fn test_transfer_builder() {
+	use manta_asset::MantaAssetFullReceiver;
+
+	let mut rng = ark_std::test_rng();
+	let witness = random_transfer_witness([10, 20, 0, 30], &mut rng);
+	let pool = [witness.sender_1.clone(), witness.sender_2.clone()];
+	let mut address = || {
+		let mut sk = [0u8; 32];
+		rng.fill_bytes(&mut sk);
+		MantaAssetFullReceiver::sample_for_network(
+			&witness.commit_param,
+			&sk,
+			&TEST_ASSET,
+			&TEST_NETWORK,
+			&mut rng,
+		)
+		.unwrap()
+		.shielded_address
+	};
+	let builder = |amount: u64, recipient, change| {
+		TransferBuilder::new(
+			witness.commit_param.clone(),
+			witness.hash_param.clone(),
+			recipient,
+			amount,
+			change,
+		)
+	};
+	let (recipient, change) = (address(), address());
+
+	// a single input is padded with a dummy one
+	let built = builder(15, recipient, change)
+		.build_witness(&pool, &mut rng)
+		.unwrap();
+	assert_eq!(built.sender_1.asset.priv_info.value, 20);
+	assert_eq!(built.sender_2.asset.priv_info.value, 0);
+	assert_eq!((built.receiver_1.value, built.receiver_2.value), (15, 5));
+
+	// two inputs are selected when no single one covers the amount
+	let built = builder(25, recipient, change)
+		.build_witness(&pool, &mut rng)
+		.unwrap();
+	assert_eq!(built.sender_1.asset.priv_info.value, 10);
+	assert_eq!(built.sender_2.asset.priv_info.value, 20);
+	assert_eq!((built.receiver_1.value, built.receiver_2.value), (25, 5));
+
+	assert!(matches!(
+		builder(31, recipient, change).build_witness(&pool, &mut rng),
+		Err(BuildError::InsufficientFunds { total: 31 })
+	));
+
+	// the fee is covered by the inputs, and reported in the total
+	let built = builder(15, recipient, change)
+		.with_fee(10)
+		.build_witness(&pool, &mut rng)
+		.unwrap();
+	assert_eq!(built.sender_1.asset.priv_info.value, 10);
+	assert_eq!(built.sender_2.asset.priv_info.value, 20);
+	assert_eq!((built.receiver_1.value, built.receiver_2.value), (15, 5));
+	assert!(matches!(
+		builder(25, recipient, change)
+			.with_fee(6)
+			.build_witness(&pool, &mut rng),
+		Err(BuildError::InsufficientFunds { total: 31 })
+	));
+	assert!(matches!(
+		builder(u64::MAX, recipient, change)
+			.with_fee(1)
+			.build_witness(&pool, &mut rng),
+		Err(BuildError::ValueOverflow)
+	));
+
+	// a testnet address cannot be paid with mainnet change
+	let mut mainnet = change;
+	mainnet.network_id = TEST_NETWORK + 1;
+	assert!(matches!(
+		builder(15, recipient, mainnet).build_witness(&pool, &mut rng),
+		Err(BuildError::MixedNetworks)
+	));
+}
+
+#[test]
+fn test_plan_strategies() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let assets: Vec<MantaAsset> = [1, 2, 3, 50, 7]
+		.iter()
+		.map(|&value| random_asset(&commit_param, value, &mut rng))
+		.collect();
+
+	// the two largest notes cover the amount in a single payment
+	let planned = plan(
+		&assets,
+		TEST_ASSET,
+		55,
+		0,
+		PaymentKind::Transfer,
+		&MinTransactions,
+	)
+	.unwrap();
+	assert_eq!(
+		planned.steps,
+		ark_std::vec![PlanStep::Payment {
+			kind: PaymentKind::Transfer,
+			inputs: [PlanInput::Owned(3), PlanInput::Owned(4)],
+			amount: 55,
+			fee: 0,
+			change: 2,
+		}]
+	);
+
+	// all the notes are spent, smallest first
+	let planned = plan(&assets, TEST_ASSET, 55, 0, PaymentKind::Reclaim, &MinDust).unwrap();
+	assert_eq!(planned.transaction_count(), 4);
+	assert_eq!(
+		planned.steps[0],
+		PlanStep::Merge {
+			inputs: [PlanInput::Owned(0), PlanInput::Owned(1)],
+			value: 3,
+		}
+	);
+	assert_eq!(
+		planned.steps[3],
+		PlanStep::Payment {
+			kind: PaymentKind::Reclaim,
+			inputs: [PlanInput::Merged(2), PlanInput::Owned(3)],
+			amount: 55,
+			fee: 0,
+			change: 8,
+		}
+	);
+
+	// the notes are spent in the order they were received
+	let planned = plan(
+		&assets,
+		TEST_ASSET,
+		55,
+		0,
+		PaymentKind::Transfer,
+		&OldestFirst,
+	)
+	.unwrap();
+	assert_eq!(planned.transaction_count(), 3);
+	assert_eq!(planned.spent_notes(), ark_std::vec![0, 1, 2, 3]);
+
+	assert_eq!(
+		plan(&assets, TEST_ASSET, 64, 0, PaymentKind::Transfer, &MinDust),
+		Err(PlanError::InsufficientFunds {
+			available: 63,
+			total: 64,
+		})
+	);
+
+	// the fee is planned for along with the amount
+	let planned = plan(
+		&assets,
+		TEST_ASSET,
+		50,
+		5,
+		PaymentKind::Transfer,
+		&MinTransactions,
+	)
+	.unwrap();
+	assert_eq!(
+		planned.steps,
+		ark_std::vec![PlanStep::Payment {
+			kind: PaymentKind::Transfer,
+			inputs: [PlanInput::Owned(3), PlanInput::Owned(4)],
+			amount: 50,
+			fee: 5,
+			change: 2,
+		}]
+	);
+	assert_eq!(
+		plan(&assets, TEST_ASSET, 60, 4, PaymentKind::Transfer, &MinDust),
+		Err(PlanError::InsufficientFunds {
+			available: 63,
+			total: 64,
+		})
+	);
+	assert_eq!(
+		plan(
+			&assets,
+			TEST_ASSET,
+			u64::MAX,
+			1,
+			PaymentKind::Transfer,
+			&MinDust
+		),
+		Err(PlanError::ValueOverflow)
+	);
+	assert_eq!(
+		plan(&assets, TEST_ASSET, 50, 5, PaymentKind::Reclaim, &MinDust),
+		Err(PlanError::ReclaimFee)
+	);
+}
+
+#[cfg(feature = "prover")]
+#[test]
+fn test_dummy_input() {
+	let mut rng = ark_std::test_rng();
+	let mut witness = random_transfer_witness([10, 20, 10, 0], &mut rng);
+	let dummy = SenderMetaData::dummy(
+		&witness.commit_param,
+		TEST_ASSET,
+		witness.sender_1.root,
+		&mut rng,
+	)
+	.unwrap();
+	assert!(dummy.is_dummy());
+	assert!(!witness.sender_1.is_dummy());
+
+	// the dummy replaces a real input without a membership of its own
+	witness.sender_2 = dummy;
+	assert_eq!(witness.check_relations(), Ok(()));
+}
+
+#[test]
+fn test_multi_arity() {
+	let mut rng = ark_std::test_rng();
+	let transfer = random_transfer_data(&mut rng);
+
+	// 2-in 2-out is the private transfer
+	let multi = MultiTransferData::<2, 2>::from(transfer.clone());
+	assert_eq!(multi.public_inputs(), transfer.public_inputs());
+	assert_eq!(
+		MultiTransferData::<2, 2>::PUBLIC_INPUT_LEN,
+		input_len(PRIVATE_TRANSFER_INPUT_LAYOUT)
+	);
+	let mut buf = Vec::new();
+	multi.serialize(&mut buf).unwrap();
+	let mut expected = Vec::new();
+	transfer.serialize(&mut expected).unwrap();
+	assert_eq!(buf, expected);
+	assert_eq!(buf.len(), MultiTransferData::<2, 2>::PAYLOAD_SIZE);
+	assert_eq!(buf.len(), PRIVATE_TRANSFER_PAYLOAD_SIZE);
+
+	// 4-in 4-out round trips through its payload
+	let batch = BatchTransferData {
+		network_id: transfer.network_id,
+		asset_id: transfer.asset_id,
+		fee: transfer.fee,
+		senders: [
+			transfer.sender_1,
+			transfer.sender_2,
+			transfer.sender_2,
+			transfer.sender_1,
+		],
+		receivers: [
+			transfer.receiver_1,
+			transfer.receiver_2,
+			transfer.receiver_1,
+			transfer.receiver_2,
+		],
+		proof: [0u8; 192],
+	};
+	let mut buf = Vec::new();
+	batch.serialize(&mut buf).unwrap();
+	assert_eq!(buf.len(), BatchTransferData::PAYLOAD_SIZE);
+	assert_eq!(
+		BatchTransferData::deserialize(buf.as_slice()).unwrap(),
+		batch
+	);
+	assert_eq!(
+		batch.public_inputs().unwrap().len(),
+		BatchTransferData::PUBLIC_INPUT_LEN
+	);
+
+	// the key is selected by the arity
+	let keys = VerificationKeys::default();
+	assert_eq!(
+		batch.verify_with(&keys),
+		Err(VerificationError::UnsupportedArity {
+			inputs: 4,
+			outputs: 4,
+		})
+	);
+	// and a key is only supported for the arity of its public inputs
+	let pk = public_input_key(MultiTransferData::<2, 2>::PUBLIC_INPUT_LEN, &mut rng);
+	let data = verification_key(&pk.vk).data;
+	let key = || VerificationKey { data };
+	let mismatch = Err(VerificationError::KeyMismatch {
+		expected: BatchTransferData::PUBLIC_INPUT_LEN,
+		found: MultiTransferData::<2, 2>::PUBLIC_INPUT_LEN,
+	});
+	let mut keys = VerificationKeys::default();
+	assert_eq!(
+		keys.insert_transfer(BatchTransferData::ARITY, key()),
+		mismatch
+	);
+	assert_eq!(batch.try_verify(&key()), mismatch);
+	assert_eq!(
+		keys.insert_transfer(MultiTransferData::<2, 2>::ARITY, key()),
+		Ok(())
+	);
+	assert!(keys.transfer_key(BatchTransferData::ARITY).is_none());
+
+	let mut broken = batch;
+	broken.senders[2].root = [0xffu8; 32];
+	assert_eq!(
+		broken.public_inputs(),
+		Err(VerificationError::MalformedEntry {
+			field: "senders.root",
+			index: 2,
+		})
+	);
+}
+
+impl FeeSink for Vec<(AssetId, u64)> {
+	fn credit(&mut self, asset_id: AssetId, amount: u64) {
+		self.push((asset_id, amount));
+	}
+}
+
+#[test]
+fn test_apply_transfer_fee() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut ledger = MantaPrivateAssetLedger::default();
+	let cm = random_commitment(0, &mut rng);
+	ledger.update(&cm, hash_param.clone()).unwrap();
+
+	let mut transfer = random_transfer_data(&mut rng);
+	transfer.fee = 3;
+	transfer.sender_1.root = *ledger.shard(0).root();
+	transfer.sender_2.root = *ledger.shard(0).root();
+
+	let mut sink: Vec<(AssetId, u64)> = Vec::new();
+	transfer
+		.apply(&mut ledger, &hash_param, TEST_NETWORK, &mut sink)
+		.unwrap();
+	assert!(ledger.exist(&transfer.receiver_1.cm));
+	assert!(ledger.exist(&transfer.receiver_2.cm));
+	assert_eq!(sink, ark_std::vec![(TEST_ASSET, 3)]);
+
+	// a rejected transfer neither posts its outputs nor pays its fee
+	let snapshot = ledger.clone();
+	let mut replay = transfer.clone();
+	replay.receiver_1 = random_transfer_data(&mut rng).receiver_1;
+	assert!(matches!(
+		replay.apply(&mut ledger, &hash_param, TEST_NETWORK, &mut sink),
+		Err(TransitionError::Ledger(
+			LedgerError::DuplicateCommitment { .. }
+		))
+	));
+	assert_eq!(ledger, snapshot);
+	assert_eq!(sink.len(), 1);
+
+	replay.sender_2.root = [1u8; 32];
+	assert!(matches!(
+		replay.apply(&mut ledger, &hash_param, TEST_NETWORK, &mut sink),
+		Err(TransitionError::Ledger(LedgerError::UnknownRoot))
+	));
+
+	// a transfer for another network is rejected
+	let mut replay = transfer.clone();
+	replay.receiver_1 = random_transfer_data(&mut rng).receiver_1;
+	replay.receiver_2 = random_transfer_data(&mut rng).receiver_2;
+	assert!(matches!(
+		replay.apply(&mut ledger, &hash_param, TEST_NETWORK + 1, &mut sink),
+		Err(TransitionError::WrongNetwork {
+			expected,
+			found: TEST_NETWORK,
+		}) if expected == TEST_NETWORK + 1
+	));
+	assert_eq!(ledger, snapshot);
+	assert_eq!(sink.len(), 1);
+}
+
+#[test]
+fn test_transaction_envelope() {
+	let mut rng = ark_std::test_rng();
+	let commit_param = CommitmentParam::deserialize(COMMIT_PARAM.data).unwrap();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let transfer = random_transfer_data(&mut rng);
+	let asset = random_asset(&commit_param, 10, &mut rng);
+	let mint = MintData {
+		network_id: TEST_NETWORK,
+		asset_id: asset.asset_id,
+		amount: asset.priv_info.value,
+		cm: asset.utxo,
+		k: asset.pub_info.k,
+		s: asset.pub_info.s,
+	};
+	let reclaim = ReclaimData {
+		network_id: TEST_NETWORK,
+		asset_id: TEST_ASSET,
+		reclaim_amount: 10,
+		recipient: [7u8; 32],
+		sender_1: transfer.sender_1,
+		sender_2: transfer.sender_2,
+		receiver: transfer.receiver_1,
+		proof: [0u8; 192],
+	};
+
+	// every kind of transaction round trips through the envelope
+	let payload_sizes = [
+		MINT_PAYLOAD_SIZE,
+		PRIVATE_TRANSFER_PAYLOAD_SIZE,
+		RECLAIM_PAYLOAD_SIZE,
+	];
+	let transactions = [
+		MantaTransaction::from(mint),
+		MantaTransaction::from(transfer),
+		MantaTransaction::from(reclaim),
+	];
+	for (tag, transaction) in transactions.iter().enumerate() {
+		let mut buf = Vec::new();
+		transaction.serialize(&mut buf).unwrap();
+		assert_eq!(buf[..2], [TRANSACTION_VERSION, tag as u8]);
+		assert_eq!(buf.len(), 2 + payload_sizes[tag]);
+		assert_eq!(
+			MantaTransaction::deserialize(buf.as_slice()).unwrap(),
+			*transaction
+		);
+		assert!(transaction.sanity(&commit_param).unwrap());
+	}
+
+	// an unknown version or tag, or a truncated payload, is rejected
+	let mut buf = Vec::new();
+	transactions[0].serialize(&mut buf).unwrap();
+	assert!(matches!(
+		MantaTransaction::decode(&buf[..buf.len() - 1]),
+		Err(DecodeError::Malformed(_))
+	));
+	buf[1] = 3;
+	assert!(MantaTransaction::deserialize(buf.as_slice()).is_err());
+	assert!(matches!(
+		MantaTransaction::decode(buf.as_slice()),
+		Err(DecodeError::UnknownTag(3))
+	));
+	buf[0] = TRANSACTION_VERSION + 1;
+	buf[1] = 0;
+	assert!(MantaTransaction::deserialize(buf.as_slice()).is_err());
+	assert!(matches!(
+		MantaTransaction::decode(buf.as_slice()),
+		Err(DecodeError::UnsupportedVersion(v)) if v == TRANSACTION_VERSION + 1
+	));
+
+	// a mint needs no key, but its commitment must open to its amount
+	let keys = VerificationKeys::default();
+	assert_eq!(
+		transactions[0].try_verify(&keys, &commit_param, TEST_NETWORK),
+		Ok(())
+	);
+	assert!(transactions[1]
+		.try_verify(&keys, &commit_param, TEST_NETWORK)
+		.is_err());
+	let mut forged = mint;
+	forged.amount += 1;
+	assert_eq!(
+		MantaTransaction::from(forged).try_verify(&keys, &commit_param, TEST_NETWORK),
+		Err(VerificationError::InvalidMint)
+	);
+	let mut ledger = MantaPrivateAssetLedger::default();
+	let mut sink: Vec<(AssetId, u64)> = Vec::new();
+
+	// no transaction is verified or applied on another network
+	for transaction in transactions.iter() {
+		assert_eq!(
+			transaction.try_verify(&keys, &commit_param, TEST_NETWORK + 1),
+			Err(VerificationError::WrongNetwork {
+				expected: TEST_NETWORK + 1,
+				found: TEST_NETWORK,
+			})
+		);
+	}
+	assert!(matches!(
+		transactions[0].apply(&mut ledger, &hash_param, TEST_NETWORK + 1, &mut sink),
+		Err(TransitionError::WrongNetwork { .. })
+	));
+	assert!(!ledger.exist(&asset.utxo));
+
+	// the mint is posted to the ledger
+	transactions[0]
+		.apply(&mut ledger, &hash_param, TEST_NETWORK, &mut sink)
+		.unwrap();
+	assert!(ledger.exist(&asset.utxo));
+	assert!(sink.is_empty());
+}
+
+#[test]
+fn test_transaction_id() {
+	let mut rng = ark_std::test_rng();
+	let transfer = random_transfer_data(&mut rng);
+	let hash = transfer.transaction_hash().unwrap();
+	let id = transfer.transaction_id().unwrap();
+	assert_eq!(transfer.clone().transaction_hash().unwrap(), hash);
+	assert_ne!(hash, id);
+
+	// a malleated proof changes the hash, but not the id
+	let mut malleated = transfer.clone();
+	malleated.proof[0] ^= 1;
+	assert_ne!(malleated.transaction_hash().unwrap(), hash);
+	assert_eq!(malleated.transaction_id().unwrap(), id);
+
+	// so do rewritten fields of the receivers that the proof does not bind
+	let mut rewritten = transfer.clone();
+	rewritten.receiver_1.k[0] ^= 1;
+	rewritten.receiver_1.sender_pk[0] ^= 1;
+	rewritten.receiver_2.cipher[0] ^= 1;
+	assert_ne!(rewritten.transaction_hash().unwrap(), hash);
+	assert_eq!(rewritten.transaction_id().unwrap(), id);
+
+	// any other field changes both
+	let mut other = transfer.clone();
+	other.fee += 1;
+	assert_ne!(other.transaction_hash().unwrap(), hash);
+	assert_ne!(other.transaction_id().unwrap(), id);
+	let mut other = transfer.clone();
+	other.receiver_2.cm = random_transfer_data(&mut rng).receiver_2.cm;
+	assert_ne!(other.transaction_hash().unwrap(), hash);
+	assert_ne!(other.transaction_id().unwrap(), id);
+
+	// the envelope has the ids of the transaction it wraps
+	let transaction = MantaTransaction::from(transfer);
+	assert_eq!(transaction.transaction_hash().unwrap(), hash);
+	assert_eq!(transaction.transaction_id().unwrap(), id);
+}
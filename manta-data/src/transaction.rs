@@ -0,0 +1,204 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements `MantaTransaction`, an envelope over the
+//! transactions of the private asset ledger.
+//!
+//! A serialized transaction starts with a header of two bytes:
+//!
+//! | field   | size | value                                     |
+//! |---------|------|-------------------------------------------|
+//! | version | 1    | `TRANSACTION_VERSION`                     |
+//! | tag     | 1    | 0: mint, 1: private transfer, 2: reclaim  |
+//!
+//! followed by the payload of the transaction, e.g., the
+//! `PRIVATE_TRANSFER_PAYLOAD_SIZE` bytes of a private transfer.
+
+use super::*;
+use ark_std::{
+	boxed::Box,
+	io::{Read, Write},
+};
+use manta_asset::SanityCheck;
+use manta_crypto::{CommitmentParam, HashParam, MantaSerDes};
+use manta_error::MantaError;
+use manta_ledger::MantaPrivateAssetLedger;
+
+/// The version of the encoding of `MantaTransaction`.
+pub const TRANSACTION_VERSION: u8 = 1;
+
+/// The tags of the kinds of transactions.
+pub(crate) const MINT_TAG: u8 = 0;
+pub(crate) const PRIVATE_TRANSFER_TAG: u8 = 1;
+pub(crate) const RECLAIM_TAG: u8 = 2;
+
+/// A transaction of the private asset ledger.
+/// Private transfers and reclaims are boxed, as they are several times
+/// the size of a mint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MantaTransaction {
+	Mint(MintData),
+	PrivateTransfer(Box<PrivateTransferData>),
+	Reclaim(Box<ReclaimData>),
+}
+
+impl MantaTransaction {
+	/// The tag of the kind of this transaction.
+	pub fn tag(&self) -> u8 {
+		match self {
+			MantaTransaction::Mint(_) => MINT_TAG,
+			MantaTransaction::PrivateTransfer(_) => PRIVATE_TRANSFER_TAG,
+			MantaTransaction::Reclaim(_) => RECLAIM_TAG,
+		}
+	}
+
+	/// The network that this transaction is for.
+	pub fn network_id(&self) -> NetworkId {
+		match self {
+			MantaTransaction::Mint(data) => data.network_id,
+			MantaTransaction::PrivateTransfer(data) => data.network_id,
+			MantaTransaction::Reclaim(data) => data.network_id,
+		}
+	}
+
+	/// Verify the proof of this transaction with the key of its kind in `keys`,
+	/// on the network `expected`; a transaction for another network is rejected.
+	/// A mint has no proof; its commitment is checked against `param` instead.
+	pub fn try_verify(
+		&self,
+		keys: &VerificationKeys,
+		param: &CommitmentParam,
+		expected: NetworkId,
+	) -> Result<(), VerificationError> {
+		let found = self.network_id();
+		if found != expected {
+			return Err(VerificationError::WrongNetwork { expected, found });
+		}
+		match self {
+			MantaTransaction::Mint(data) => match data.sanity(param) {
+				Ok(true) => Ok(()),
+				_ => Err(VerificationError::InvalidMint),
+			},
+			MantaTransaction::PrivateTransfer(data) => {
+				let arity = MultiTransferData::<2, 2>::ARITY;
+				let key = keys
+					.transfer_key(arity)
+					.ok_or(VerificationError::UnsupportedArity {
+						inputs: arity.inputs,
+						outputs: arity.outputs,
+					})?;
+				data.try_verify(key)
+			}
+			MantaTransaction::Reclaim(data) => {
+				let arity = MultiReclaimData::<2, 1>::ARITY;
+				let key = keys
+					.reclaim_key(arity)
+					.ok_or(VerificationError::UnsupportedArity {
+						inputs: arity.inputs,
+						outputs: arity.outputs,
+					})?;
+				data.try_verify(key)
+			}
+		}
+	}
+
+	/// Apply this transaction to `ledger` of the network `expected`; the fee
+	/// of a private transfer is credited to `sink`. See the `apply` method
+	/// of each transaction.
+	pub fn apply<S: FeeSink>(
+		&self,
+		ledger: &mut MantaPrivateAssetLedger,
+		param: &HashParam,
+		expected: NetworkId,
+		sink: &mut S,
+	) -> Result<(), TransitionError> {
+		match self {
+			MantaTransaction::Mint(data) => data.apply(ledger, param, expected),
+			MantaTransaction::PrivateTransfer(data) => data.apply(ledger, param, expected, sink),
+			MantaTransaction::Reclaim(data) => data.apply(ledger, param, expected),
+		}
+	}
+}
+
+impl SanityCheck for MantaTransaction {
+	type Param = CommitmentParam;
+
+	/// Check if the transaction is well-formed: the commitment of a mint
+	/// opens to its amount, and the fields of a private transfer or of a
+	/// reclaim encode valid public inputs.
+	fn sanity(&self, param: &Self::Param) -> Result<bool, MantaError> {
+		match self {
+			MantaTransaction::Mint(data) => data.sanity(param),
+			MantaTransaction::PrivateTransfer(data) => Ok(data.public_inputs().is_ok()),
+			MantaTransaction::Reclaim(data) => Ok(data.public_inputs().is_ok()),
+		}
+	}
+}
+
+impl MantaSerDes for MantaTransaction {
+	/// Serialize the transaction, with its header.
+	fn serialize<W: Write>(&self, mut writer: W) -> Result<(), MantaError> {
+		writer.write_all(&[TRANSACTION_VERSION, self.tag()])?;
+		match self {
+			MantaTransaction::Mint(data) => data.serialize(writer),
+			MantaTransaction::PrivateTransfer(data) => data.serialize(writer),
+			MantaTransaction::Reclaim(data) => data.serialize(writer),
+		}
+	}
+
+	/// Deserialize a transaction; an unknown version or tag is rejected.
+	/// See `MantaTransaction::decode` for the reason of a rejection.
+	fn deserialize<R: Read>(reader: R) -> Result<Self, MantaError> {
+		Ok(Self::decode(reader)?)
+	}
+}
+
+impl MantaTransaction {
+	/// Deserialize a transaction, and report whether its version, its tag,
+	/// or its payload is rejected.
+	pub fn decode<R: Read>(mut reader: R) -> Result<Self, DecodeError> {
+		let mut header = [0u8; 2];
+		reader.read_exact(&mut header).map_err(MantaError::from)?;
+		if header[0] != TRANSACTION_VERSION {
+			return Err(DecodeError::UnsupportedVersion(header[0]));
+		}
+
+		match header[1] {
+			MINT_TAG => Ok(MintData::deserialize(reader)?.into()),
+			PRIVATE_TRANSFER_TAG => Ok(PrivateTransferData::deserialize(reader)?.into()),
+			RECLAIM_TAG => Ok(ReclaimData::deserialize(reader)?.into()),
+			tag => Err(DecodeError::UnknownTag(tag)),
+		}
+	}
+}
+
+impl From<MintData> for MantaTransaction {
+	fn from(data: MintData) -> Self {
+		MantaTransaction::Mint(data)
+	}
+}
+
+impl From<PrivateTransferData> for MantaTransaction {
+	fn from(data: PrivateTransferData) -> Self {
+		MantaTransaction::PrivateTransfer(Box::new(data))
+	}
+}
+
+impl From<ReclaimData> for MantaTransaction {
+	fn from(data: ReclaimData) -> Self {
+		MantaTransaction::Reclaim(Box::new(data))
+	}
+}
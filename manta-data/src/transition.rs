@@ -0,0 +1,104 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the transition of the private asset ledger
+//! by mints, private transfers and reclaims.
+//!
+//! The transition checks that the transaction is for the network of the
+//! ledger, checks the roots of the transaction against the ledger, and
+//! posts the commitments of its outputs. A private transfer also
+//! credits its fee to a `FeeSink`, e.g. the account of the relayer that
+//! submitted it. The public side of mints and reclaims, i.e., debiting the
+//! minted amount and crediting the reclaimed amount to its recipient, is
+//! left to the caller.
+//! The proof and the void numbers of the transaction are not checked here;
+//! the caller verifies the proof, and rejects spent void numbers, before
+//! applying the transaction.
+
+use super::*;
+use manta_crypto::HashParam;
+use manta_ledger::{LedgerError, LedgerSharding, MantaPrivateAssetLedger};
+
+/// Check that a transaction for `found` is applied on the network `expected`.
+fn check_network(expected: NetworkId, found: NetworkId) -> Result<(), TransitionError> {
+	if found != expected {
+		return Err(TransitionError::WrongNetwork { expected, found });
+	}
+	Ok(())
+}
+
+/// The recipient of the public fees of private transfers.
+pub trait FeeSink {
+	/// Credit `amount` of `asset_id` to the sink.
+	fn credit(&mut self, asset_id: AssetId, amount: u64);
+}
+
+impl PrivateTransferData {
+	/// Apply this transfer to `ledger` of the network `expected`, and
+	/// credit its fee to `sink`.
+	/// The ledger and the sink are left untouched if the transfer is rejected.
+	pub fn apply<S: FeeSink>(
+		&self,
+		ledger: &mut MantaPrivateAssetLedger,
+		param: &HashParam,
+		expected: NetworkId,
+		sink: &mut S,
+	) -> Result<(), TransitionError> {
+		check_network(expected, self.network_id)?;
+		if !ledger.check_root(&self.sender_1.root) || !ledger.check_root(&self.sender_2.root) {
+			return Err(LedgerError::UnknownRoot.into());
+		}
+
+		ledger.update_all(&[self.receiver_1.cm, self.receiver_2.cm], param)?;
+		if self.fee > 0 {
+			sink.credit(self.asset_id, self.fee);
+		}
+		Ok(())
+	}
+}
+
+impl MintData {
+	/// Apply this mint to `ledger` of the network `expected`.
+	/// The network id of a mint is not bound into its commitment, so a
+	/// mint for another network is rejected here.
+	pub fn apply(
+		&self,
+		ledger: &mut MantaPrivateAssetLedger,
+		param: &HashParam,
+		expected: NetworkId,
+	) -> Result<(), TransitionError> {
+		check_network(expected, self.network_id)?;
+		Ok(ledger.try_update(&self.cm, param)?)
+	}
+}
+
+impl<AccountId> ReclaimData<AccountId> {
+	/// Apply this reclaim to `ledger` of the network `expected`.
+	/// The ledger is left untouched if the reclaim is rejected.
+	pub fn apply(
+		&self,
+		ledger: &mut MantaPrivateAssetLedger,
+		param: &HashParam,
+		expected: NetworkId,
+	) -> Result<(), TransitionError> {
+		check_network(expected, self.network_id)?;
+		if !ledger.check_root(&self.sender_1.root) || !ledger.check_root(&self.sender_2.root) {
+			return Err(LedgerError::UnknownRoot.into());
+		}
+
+		Ok(ledger.try_update(&self.receiver.cm, param)?)
+	}
+}
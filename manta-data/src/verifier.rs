@@ -0,0 +1,310 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a verifier that prepares its verification key once,
+//! to verify many private transfers or reclaims against the same key.
+//!
+//! A batch of `n` proofs under the same key is verified with a random
+//! linear combination of the `n` Groth16 equations
+//!   `e(A_i, B_i) = e(alpha, beta) * e(IC(x_i), gamma) * e(C_i, delta)`,
+//! i.e., with random `r_i`, the single check
+//!   `prod_i e(r_i A_i, B_i) * e(sum_i r_i IC(x_i), -gamma) * e(sum_i r_i C_i, -delta)
+//!     = e(alpha, beta)^(sum_i r_i)`,
+//! which costs `n + 2` miller loops and one final exponentiation.
+//!
+//! Every `try_verify` goes through a `PreparedVerifier` as well, so that
+//! `MOCK_VERIFICATION_KEY` is recognised in a single place, when the key
+//! is prepared, with the `mock-proofs` feature.
+
+use super::*;
+use crate::zkp::{check_proof, deserialize_proof};
+use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_groth16::prepare_inputs;
+use ark_serialize::CanonicalDeserialize;
+use ark_std::{
+	rand::{CryptoRng, RngCore},
+	vec::Vec,
+};
+use manta_crypto::{Groth16Proof, Groth16Pvk, Groth16Vk, VerificationKey};
+
+/// A proof and its public inputs, or the error of building them.
+type BatchItem<'a> = Result<(&'a [u8; 192], Vec<Fr>), VerificationError>;
+
+/// A verifier that holds a prepared verification key.
+/// Unlike `MantaZKPVerifier::verify`, which deserializes and prepares the key
+/// for every proof, the key is deserialized, checked, and prepared once
+/// when the verifier is built.
+/// The verifier checks the public inputs of the `PayloadVersion` of its key.
+#[derive(Clone)]
+pub struct PreparedVerifier {
+	key: PreparedKey,
+	version: PayloadVersion,
+}
+
+/// A prepared verification key.
+#[derive(Clone)]
+enum PreparedKey {
+	/// A Groth16 key.
+	Groth16(Groth16Pvk),
+	/// `MOCK_VERIFICATION_KEY`, which checks marker proofs.
+	#[cfg(feature = "mock-proofs")]
+	Mock,
+}
+
+impl PreparedVerifier {
+	/// Build a verifier from the bytes of a verification key, in the
+	/// uncompressed encoding of the published keys.
+	/// The key is deserialized with all the curve and subgroup checks.
+	/// With the `mock-proofs` feature, `MOCK_VERIFICATION_KEY` builds
+	/// a verifier of marker proofs.
+	/// The key is for the circuits of `PayloadVersion::CURRENT`.
+	pub fn new(key: &VerificationKey) -> Result<Self, VerificationError> {
+		Self::prepare(key, true, PayloadVersion::CURRENT)
+	}
+
+	/// Build a verifier from the bytes of a verification key for the
+	/// circuits of `version`, e.g., `PayloadVersion::V0` for the
+	/// published keys, as for `new`.
+	pub fn new_version(
+		key: &VerificationKey,
+		version: PayloadVersion,
+	) -> Result<Self, VerificationError> {
+		Self::prepare(key, true, version)
+	}
+
+	/// Build a verifier from the bytes of a verification key for the
+	/// circuits of `version`, without the curve and subgroup checks,
+	/// as `try_verify` does.
+	pub(crate) fn new_unchecked(
+		key: &VerificationKey,
+		version: PayloadVersion,
+	) -> Result<Self, VerificationError> {
+		Self::prepare(key, false, version)
+	}
+
+	/// Build a verifier from a verification key that is already deserialized,
+	/// for the circuits of `PayloadVersion::CURRENT`.
+	pub fn from_vk(vk: Groth16Vk) -> Self {
+		Self {
+			key: PreparedKey::Groth16(Groth16Pvk::from(vk)),
+			version: PayloadVersion::CURRENT,
+		}
+	}
+
+	/// The version of the circuits of the key.
+	pub fn version(&self) -> PayloadVersion {
+		self.version
+	}
+
+	/// Build a verifier from the bytes of a verification key,
+	/// with the curve and subgroup checks if `checked`.
+	fn prepare(
+		key: &VerificationKey,
+		checked: bool,
+		version: PayloadVersion,
+	) -> Result<Self, VerificationError> {
+		#[cfg(feature = "mock-proofs")]
+		{
+			if crate::mock::is_mock_key(key) {
+				return Ok(Self {
+					key: PreparedKey::Mock,
+					version,
+				});
+			}
+		}
+		let buf: &[u8] = key.data;
+		let vk = if checked {
+			Groth16Vk::deserialize_uncompressed(buf)
+		} else {
+			Groth16Vk::deserialize_unchecked(buf)
+		};
+		let vk = vk.map_err(|_| VerificationError::InvalidVerificationKey)?;
+		Ok(Self {
+			key: PreparedKey::Groth16(Groth16Pvk::from(vk)),
+			version,
+		})
+	}
+
+	/// Verify `proof` against its public inputs.
+	pub(crate) fn verify_proof(
+		&self,
+		proof: &[u8; 192],
+		inputs: &[Fr],
+	) -> Result<(), VerificationError> {
+		self.check_input_len(inputs.len())?;
+		match &self.key {
+			PreparedKey::Groth16(pvk) => check_proof(pvk, &deserialize_proof(proof)?, inputs),
+			#[cfg(feature = "mock-proofs")]
+			PreparedKey::Mock => crate::mock::check_marker_proof(proof, inputs),
+		}
+	}
+
+	/// Check that the key is for a circuit of `len` public inputs;
+	/// the mock key takes any number of public inputs.
+	pub(crate) fn check_input_len(&self, len: usize) -> Result<(), VerificationError> {
+		match &self.key {
+			PreparedKey::Groth16(pvk) => {
+				let found = pvk.vk.gamma_abc_g1.len().saturating_sub(1);
+				if found == len {
+					Ok(())
+				} else {
+					Err(VerificationError::KeyMismatch {
+						expected: len,
+						found,
+					})
+				}
+			}
+			#[cfg(feature = "mock-proofs")]
+			PreparedKey::Mock => Ok(()),
+		}
+	}
+
+	/// Verify the proof of a private transfer.
+	pub fn verify_transfer(&self, data: &PrivateTransferData) -> Result<(), VerificationError> {
+		self.verify_proof(&data.proof, &data.public_inputs_of(self.version)?)
+	}
+
+	/// Verify the proof of a reclaim.
+	pub fn verify_reclaim<AccountId: ReclaimAccount>(
+		&self,
+		data: &ReclaimData<AccountId>,
+	) -> Result<(), VerificationError> {
+		self.verify_proof(&data.proof, &data.public_inputs_of(self.version)?)
+	}
+
+	/// Verify a batch of private transfers with a single multi-pairing.
+	/// If the batch fails, every transfer is verified on its own, and the
+	/// indices of the failing transfers are returned with their errors.
+	pub fn batch_verify_transfers<R: RngCore + CryptoRng>(
+		&self,
+		data: &[PrivateTransferData],
+		rng: &mut R,
+	) -> Result<(), Vec<(usize, VerificationError)>> {
+		self.batch_verify(
+			data.iter()
+				.map(|d| Ok((&d.proof, d.public_inputs_of(self.version)?))),
+			rng,
+		)
+	}
+
+	/// Verify a batch of reclaims with a single multi-pairing.
+	/// If the batch fails, every reclaim is verified on its own, and the
+	/// indices of the failing reclaims are returned with their errors.
+	pub fn batch_verify_reclaims<AccountId: ReclaimAccount, R: RngCore + CryptoRng>(
+		&self,
+		data: &[ReclaimData<AccountId>],
+		rng: &mut R,
+	) -> Result<(), Vec<(usize, VerificationError)>> {
+		self.batch_verify(
+			data.iter()
+				.map(|d| Ok((&d.proof, d.public_inputs_of(self.version)?))),
+			rng,
+		)
+	}
+
+	/// Verify a batch of proofs with their public inputs.
+	/// Items that cannot be deserialized are reported right away,
+	/// and the remaining ones go through the batched check.
+	/// Marker proofs have no pairing to batch, and are checked one by one.
+	fn batch_verify<'a, I, R>(
+		&self,
+		items: I,
+		rng: &mut R,
+	) -> Result<(), Vec<(usize, VerificationError)>>
+	where
+		I: Iterator<Item = BatchItem<'a>>,
+		R: RngCore + CryptoRng,
+	{
+		let pvk = match &self.key {
+			PreparedKey::Groth16(pvk) => pvk,
+			#[cfg(feature = "mock-proofs")]
+			PreparedKey::Mock => {
+				let failures: Vec<_> = items
+					.map(|item| item.and_then(|(proof, inputs)| self.verify_proof(proof, &inputs)))
+					.enumerate()
+					.filter_map(|(index, result)| result.err().map(|e| (index, e)))
+					.collect();
+				return if failures.is_empty() {
+					Ok(())
+				} else {
+					Err(failures)
+				};
+			}
+		};
+
+		let mut failures = Vec::new();
+		let mut batch = Vec::new();
+		for (index, item) in items.enumerate() {
+			let item = item.and_then(|(proof, inputs)| {
+				self.check_input_len(inputs.len())?;
+				Ok((deserialize_proof(proof)?, inputs))
+			});
+			match item {
+				Ok(p) => batch.push((index, p)),
+				Err(e) => failures.push((index, e)),
+			}
+		}
+
+		if batch_check(pvk, &batch, rng) != Ok(true) {
+			// fall back to the individual checks to find the failing proofs
+			for (index, (proof, inputs)) in batch.iter() {
+				if let Err(e) = check_proof(pvk, proof, inputs) {
+					failures.push((*index, e));
+				}
+			}
+			failures.sort_by_key(|(index, _)| *index);
+		}
+
+		if failures.is_empty() {
+			Ok(())
+		} else {
+			Err(failures)
+		}
+	}
+}
+
+/// The batched pairing check over a random linear combination of the proofs.
+fn batch_check<R: RngCore + CryptoRng>(
+	pvk: &Groth16Pvk,
+	batch: &[(usize, (Groth16Proof, Vec<Fr>))],
+	rng: &mut R,
+) -> Result<bool, VerificationError> {
+	let mut pairs: Vec<(
+		<Bls12_381 as PairingEngine>::G1Prepared,
+		<Bls12_381 as PairingEngine>::G2Prepared,
+	)> = Vec::with_capacity(batch.len() + 2);
+	let mut sum_inputs = G1Projective::zero();
+	let mut sum_c = G1Projective::zero();
+	let mut sum_r = Fr::zero();
+	for (_, (proof, inputs)) in batch.iter() {
+		let r = Fr::rand(rng);
+		let mut prepared_inputs =
+			prepare_inputs(&pvk, inputs).map_err(|_| VerificationError::PairingError)?;
+		prepared_inputs *= r;
+		sum_inputs += &prepared_inputs;
+		sum_c += &proof.c.mul(r);
+		sum_r += &r;
+		pairs.push((proof.a.mul(r).into_affine().into(), proof.b.into()));
+	}
+	pairs.push((sum_inputs.into_affine().into(), pvk.gamma_g2_neg_pc.clone()));
+	pairs.push((sum_c.into_affine().into(), pvk.delta_g2_neg_pc.clone()));
+
+	let qap = Bls12_381::miller_loop(pairs.iter());
+	let test = Bls12_381::final_exponentiation(&qap).ok_or(VerificationError::PairingError)?;
+	Ok(test == pvk.alpha_g1_beta_g2.pow(sum_r.into_repr()))
+}
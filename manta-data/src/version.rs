@@ -0,0 +1,115 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the versions of the payloads of transactions,
+//! and of the public inputs of the circuits that prove them.
+//!
+//! The published `TRANSFER_PK` and `RECLAIM_PK` of `manta-crypto` are the
+//! keys of the circuits of `V0`, whose public inputs are mostly those of the
+//! inputs and outputs of a transaction. The circuits of `manta-circuits`
+//! also bind the fee or the recipient, the asset id and the network id of
+//! a transaction, and their keys are those of `V1`,
+//! the current version. A key only verifies the public inputs of its
+//! version, and every version has its own payload; so the version goes
+//! along with the key, e.g., in `PreparedVerifier::new_version`, and with
+//! the payloads that are not of the current version, e.g., in
+//! `PrivateTransferData::deserialize_version`.
+
+use super::*;
+use manta_crypto::{VerificationKey, RECLAIM_PK, TRANSFER_PK};
+
+/// A version of the payloads, and of the public inputs of the circuits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadVersion {
+	/// The payloads of the published keys. A private transfer has no
+	/// network id, asset id or fee: it is decoded for the network of the
+	/// node, with the default asset id, and cannot pay a fee. A reclaim
+	/// has no network id or recipient: the recipient encodes to zeros.
+	/// A mint has no network id either.
+	V0,
+	/// The payloads of `MantaSerDes`, and of the keys generated with
+	/// `manta-circuits`.
+	V1,
+}
+
+impl PayloadVersion {
+	/// The current version.
+	pub const CURRENT: Self = PayloadVersion::V1;
+
+	/// The published key of the private transfers of this version, if any:
+	/// `TRANSFER_PK` for `V0`. The keys of `V1` are generated with
+	/// `manta-circuits`.
+	pub fn published_transfer_key(self) -> Option<VerificationKey> {
+		match self {
+			PayloadVersion::V0 => Some(VerificationKey {
+				data: TRANSFER_PK.data,
+			}),
+			PayloadVersion::V1 => None,
+		}
+	}
+
+	/// The published key of the reclaims of this version, if any:
+	/// `RECLAIM_PK` for `V0`. The keys of `V1` are generated with
+	/// `manta-circuits`.
+	pub fn published_reclaim_key(self) -> Option<VerificationKey> {
+		match self {
+			PayloadVersion::V0 => Some(VerificationKey {
+				data: RECLAIM_PK.data,
+			}),
+			PayloadVersion::V1 => None,
+		}
+	}
+
+	/// The size of the payload of a mint.
+	pub fn mint_payload_size(self) -> usize {
+		match self {
+			PayloadVersion::V0 => 112,
+			PayloadVersion::V1 => MINT_PAYLOAD_SIZE,
+		}
+	}
+
+	/// The public inputs of the private transfer circuit, in order.
+	pub fn private_transfer_input_layout(self) -> &'static [InputSegment] {
+		match self {
+			PayloadVersion::V0 => PRIVATE_TRANSFER_INPUT_LAYOUT_V0,
+			PayloadVersion::V1 => PRIVATE_TRANSFER_INPUT_LAYOUT,
+		}
+	}
+
+	/// The size of the payload of a private transfer.
+	pub fn private_transfer_payload_size(self) -> usize {
+		match self {
+			PayloadVersion::V0 => 608,
+			PayloadVersion::V1 => PRIVATE_TRANSFER_PAYLOAD_SIZE,
+		}
+	}
+
+	/// The public inputs of the reclaim circuit, in order.
+	pub fn reclaim_input_layout(self) -> &'static [InputSegment] {
+		match self {
+			PayloadVersion::V0 => RECLAIM_INPUT_LAYOUT_V0,
+			PayloadVersion::V1 => RECLAIM_INPUT_LAYOUT,
+		}
+	}
+
+	/// The size of the payload of a reclaim.
+	pub fn reclaim_payload_size(self) -> usize {
+		match self {
+			PayloadVersion::V0 => 512,
+			PayloadVersion::V1 => RECLAIM_PAYLOAD_SIZE,
+		}
+	}
+}
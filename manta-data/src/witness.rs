@@ -0,0 +1,433 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements incremental witnesses for owned notes.
+//!
+//! A `LedgerMerkleTree` over `n` leaves is a complete tree over the
+//! next power of two of `n` leaves, where missing leaves are the empty
+//! hash `e`; this tree is then padded up to `HEIGHT` by hashing its root
+//! with `e` at every level above it.
+//! A `TreeFrontier` keeps, for every level, the last left node and the
+//! node on the path of the last leaf, so that appending a leaf and
+//! computing the root cost O(depth) hashes.
+//! An `IncrementalWitness` additionally keeps the siblings on the path
+//! of one owned leaf, so that its authentication path follows the
+//! ledger at the same cost.
+//!
+//! The membership of an asset is a `MembershipPath`, which has the layout
+//! of an arkworks `Path`, i.e., the `(left, right)` pairs of nodes from the
+//! leaf up to the root, and is checked the same way. Unlike a `Path`, whose
+//! nodes are private to arkworks, it can be built from the nodes that a
+//! witness tracks, so that `IncrementalWitness::sender_metadata` yields a
+//! fresh `SenderMetaData` in O(depth) hashes per appended leaf, instead of
+//! rebuilding the tree from all of its leaves with `BuildMetadata`.
+
+use super::*;
+use ark_crypto_primitives::{
+	crh::FixedLengthCRH,
+	merkle_tree::{Config, MerkleTree as ArkMerkleTree},
+};
+use ark_ff::ToBytes;
+use ark_std::vec::Vec;
+use manta_crypto::{HashOutput, HashParam, LedgerMerkleTree};
+use manta_error::MantaError;
+use manta_ledger::{shard_index_of, LedgerDelta, LedgerError, MantaPrivateAssetLedger};
+
+/// Recover the merkle tree configuration from the `LedgerMerkleTree` alias.
+trait TreeConfig {
+	type Config: Config;
+}
+
+impl<P: Config> TreeConfig for ArkMerkleTree<P> {
+	type Config = P;
+}
+
+/// The hash function of the ledger merkle trees.
+type LedgerHash = <<LedgerMerkleTree as TreeConfig>::Config as Config>::H;
+
+/// The number of `(left, right)` pairs in a path of a ledger merkle tree.
+const PATH_LENGTH: usize = LedgerMerkleTree::HEIGHT as usize - 1;
+
+/// The hash of a leaf, as computed by `LedgerMerkleTree`.
+fn hash_leaf(param: &HashParam, leaf: &[u8; 32]) -> Result<HashOutput, MantaError> {
+	let mut buf = [0u8; 128];
+	leaf.write(buf.as_mut())?;
+	Ok(LedgerHash::evaluate(
+		param,
+		&buf[..LedgerHash::INPUT_SIZE_BITS / 8],
+	)?)
+}
+
+/// The hash of an inner node, as computed by `LedgerMerkleTree`.
+fn hash_inner_node(
+	param: &HashParam,
+	left: &HashOutput,
+	right: &HashOutput,
+) -> Result<HashOutput, MantaError> {
+	let mut buf = [0u8; 128];
+	let mut writer = buf.as_mut();
+	left.write(&mut writer)?;
+	right.write(&mut writer)?;
+	Ok(LedgerHash::evaluate(
+		param,
+		&buf[..LedgerHash::INPUT_SIZE_BITS / 8],
+	)?)
+}
+
+/// The hash of an empty leaf, as computed by `LedgerMerkleTree`.
+fn hash_empty(param: &HashParam) -> Result<HashOutput, MantaError> {
+	let buf = [0u8; 128];
+	Ok(LedgerHash::evaluate(
+		param,
+		&buf[..LedgerHash::INPUT_SIZE_BITS / 8],
+	)?)
+}
+
+/// The depth of the complete tree that holds `size` leaves.
+fn tree_depth(size: u64) -> usize {
+	size.next_power_of_two().trailing_zeros() as usize
+}
+
+/// The authentication path of a leaf of a ledger merkle tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipPath {
+	path: Vec<(HashOutput, HashOutput)>,
+}
+
+impl MembershipPath {
+	/// Build a path from its `(left, right)` pairs, from the leaf up to the
+	/// root; there is one pair per level of a `LedgerMerkleTree`.
+	pub fn new(path: Vec<(HashOutput, HashOutput)>) -> Result<Self, MantaError> {
+		if path.len() != PATH_LENGTH {
+			return Err(MantaError::SanityCheckFail);
+		}
+		Ok(Self { path })
+	}
+
+	/// The `(left, right)` pairs of the path, from the leaf up to the root.
+	pub fn path(&self) -> &[(HashOutput, HashOutput)] {
+		&self.path
+	}
+
+	/// Check that `leaf` is the leaf of the path, and that the path leads
+	/// to `root`, as the `verify` method of an arkworks `Path` does.
+	pub fn verify(
+		&self,
+		param: &HashParam,
+		root: &LedgerMerkleTreeRoot,
+		leaf: &[u8; 32],
+	) -> Result<bool, MantaError> {
+		let mut node = hash_leaf(param, leaf)?;
+		if node != self.path[0].0 && node != self.path[0].1 {
+			return Ok(false);
+		}
+		for (left, right) in self.path.iter() {
+			if node != *left && node != *right {
+				return Ok(false);
+			}
+			node = hash_inner_node(param, left, right)?;
+		}
+		Ok(node == *root)
+	}
+}
+
+/// The path of a leaf that is not on the ledger, e.g., of a dummy input;
+/// every node is the default one, as in the default arkworks `Path`.
+impl Default for MembershipPath {
+	fn default() -> Self {
+		Self {
+			path: ark_std::vec![(HashOutput::default(), HashOutput::default()); PATH_LENGTH],
+		}
+	}
+}
+
+/// The frontier of a ledger merkle tree: enough of the tree to append
+/// leaves to it and to compute its root.
+#[derive(Debug, Clone)]
+pub struct TreeFrontier {
+	size: u64,
+	// the root of an empty subtree at every level
+	empty: Vec<HashOutput>,
+	// the last left node at every level
+	filled: Vec<HashOutput>,
+	// the node on the path of the last leaf at every level
+	rightmost: Vec<HashOutput>,
+}
+
+impl TreeFrontier {
+	/// Build the frontier of an empty tree.
+	pub fn new(param: &HashParam) -> Result<Self, MantaError> {
+		let mut empty = Vec::with_capacity(PATH_LENGTH + 1);
+		empty.push(hash_empty(param)?);
+		for level in 0..PATH_LENGTH {
+			empty.push(hash_inner_node(param, &empty[level], &empty[level])?);
+		}
+
+		Ok(Self {
+			size: 0,
+			filled: empty.clone(),
+			rightmost: empty.clone(),
+			empty,
+		})
+	}
+
+	/// The number of leaves in the tree.
+	pub fn size(&self) -> u64 {
+		self.size
+	}
+
+	/// Append a leaf to the tree.
+	pub fn append(&mut self, param: &HashParam, leaf: &[u8; 32]) -> Result<(), MantaError> {
+		let index = self.size;
+		let mut node = hash_leaf(param, leaf)?;
+		for level in 0..PATH_LENGTH {
+			self.rightmost[level] = node;
+			node = if (index >> level) & 1 == 0 {
+				self.filled[level] = node;
+				hash_inner_node(param, &node, &self.empty[level])?
+			} else {
+				hash_inner_node(param, &self.filled[level], &node)?
+			};
+		}
+		self.rightmost[PATH_LENGTH] = node;
+		self.size += 1;
+		Ok(())
+	}
+
+	/// The root of the tree, which matches the root that `LedgerMerkleTree`
+	/// computes over the same leaves; an empty tree has no root.
+	pub fn root(&self, param: &HashParam) -> Result<HashOutput, MantaError> {
+		if self.size == 0 {
+			return Err(MantaError::LeavesNotFound);
+		}
+		let depth = tree_depth(self.size);
+		let mut root = self.rightmost[depth];
+		for _ in depth..PATH_LENGTH {
+			root = hash_inner_node(param, &root, &self.empty[0])?;
+		}
+		Ok(root)
+	}
+}
+
+/// An incremental witness for the membership of an owned `MantaAsset`
+/// in the current tree of its shard.
+#[derive(Debug, Clone)]
+pub struct IncrementalWitness {
+	asset: MantaAsset,
+	shard: u8,
+	// the position of the first leaf of the tree in the shard
+	tree_start: u64,
+	capacity: u64,
+	// the position of the asset in the tree
+	position: u64,
+	frontier: TreeFrontier,
+	// the left siblings on the path of the asset; they never change
+	left: Vec<Option<HashOutput>>,
+	// the right siblings on the path of the asset, as far as they are known
+	right: Vec<Option<HashOutput>>,
+}
+
+impl IncrementalWitness {
+	/// Start a witness for `asset`, right after its `utxo` is appended
+	/// to `frontier`.
+	/// `tree_start` is the position of the first leaf of the tree in its
+	/// shard, and `capacity` is the capacity of the ledger trees.
+	pub fn new(
+		param: &HashParam,
+		asset: &MantaAsset,
+		frontier: &TreeFrontier,
+		tree_start: u64,
+		capacity: u64,
+	) -> Result<Self, LedgerError> {
+		if frontier.size == 0 || frontier.rightmost[0] != hash_leaf(param, &asset.utxo)? {
+			return Err(MantaError::LeavesNotFound.into());
+		}
+
+		let position = frontier.size - 1;
+		let left = (0..PATH_LENGTH)
+			.map(|level| {
+				if (position >> level) & 1 == 1 {
+					Some(frontier.filled[level])
+				} else {
+					None
+				}
+			})
+			.collect();
+
+		Ok(Self {
+			asset: *asset,
+			shard: shard_index_of(&asset.utxo) as u8,
+			tree_start,
+			capacity,
+			position,
+			frontier: frontier.clone(),
+			left,
+			right: ark_std::vec![None; PATH_LENGTH],
+		})
+	}
+
+	/// Build the witness of `asset` from the current state of the ledger.
+	/// This replays the tree of the asset once; afterwards the witness is
+	/// kept up to date with `append` or `apply_delta`.
+	pub fn from_ledger(
+		param: &HashParam,
+		asset: &MantaAsset,
+		ledger: &MantaPrivateAssetLedger,
+	) -> Result<Self, LedgerError> {
+		let shard = ledger.shard(shard_index_of(&asset.utxo) as u8);
+		let index = match shard.list().iter().position(|x| *x == asset.utxo) {
+			Some(p) => p as u64,
+			None => return Err(MantaError::LeavesNotFound.into()),
+		};
+
+		let capacity = ledger.capacity();
+		let tree_start = index - index % capacity;
+		let tree_end = (tree_start + capacity).min(shard.len() as u64);
+		let leaves = &shard.list()[tree_start as usize..tree_end as usize];
+		Self::from_leaves(param, asset, leaves, tree_start, capacity)
+	}
+
+	/// Build the witness of `asset` from the `leaves` of its tree, which
+	/// starts at position `tree_start` of its shard.
+	pub fn from_leaves(
+		param: &HashParam,
+		asset: &MantaAsset,
+		leaves: &[[u8; 32]],
+		tree_start: u64,
+		capacity: u64,
+	) -> Result<Self, LedgerError> {
+		let index = match leaves.iter().position(|x| *x == asset.utxo) {
+			Some(p) => p,
+			None => return Err(MantaError::LeavesNotFound.into()),
+		};
+
+		let mut frontier = TreeFrontier::new(param)?;
+		let (before, after) = leaves.split_at(index + 1);
+		for leaf in before {
+			frontier.append(param, leaf)?;
+		}
+		let mut witness = Self::new(param, asset, &frontier, tree_start, capacity)?;
+		for leaf in after {
+			witness.append(param, leaf)?;
+		}
+		Ok(witness)
+	}
+
+	/// The asset this witness is for.
+	pub fn asset(&self) -> &MantaAsset {
+		&self.asset
+	}
+
+	/// The position of the asset in its shard.
+	pub fn shard_position(&self) -> (u8, u64) {
+		(self.shard, self.tree_start + self.position)
+	}
+
+	/// Whether the tree of the asset is full; a full tree is retired
+	/// by the ledger and the witness no longer changes.
+	pub fn is_full(&self) -> bool {
+		self.frontier.size >= self.capacity
+	}
+
+	/// Append the next leaf of the tree of the asset.
+	pub fn append(&mut self, param: &HashParam, leaf: &[u8; 32]) -> Result<(), LedgerError> {
+		if self.is_full() {
+			return Err(LedgerError::ShardFull {
+				shard: self.shard as usize,
+			});
+		}
+		self.frontier.append(param, leaf)?;
+
+		// refresh the right siblings that the new leaf belongs to
+		let last = self.frontier.size - 1;
+		for level in 0..PATH_LENGTH {
+			if (self.position >> level) & 1 == 0 && last >> level == (self.position >> level) + 1 {
+				self.right[level] = Some(self.frontier.rightmost[level]);
+			}
+		}
+		Ok(())
+	}
+
+	/// Apply a ledger delta: the leaves that belong to the tree of the
+	/// asset are appended, the other ones are skipped.
+	/// The delta must not skip any leaf of the tree.
+	pub fn apply_delta(
+		&mut self,
+		param: &HashParam,
+		delta: &LedgerDelta,
+	) -> Result<(), LedgerError> {
+		let tree_end = self.tree_start + self.capacity;
+		for (shard, index, cm) in delta.iter() {
+			if shard != self.shard || index < self.tree_start + self.frontier.size {
+				continue;
+			}
+			if index >= tree_end {
+				continue;
+			}
+			if index != self.tree_start + self.frontier.size {
+				return Err(LedgerError::OutOfSync {
+					shard: self.shard as usize,
+				});
+			}
+			self.append(param, &cm)?;
+		}
+		Ok(())
+	}
+
+	/// The authentication path of the asset, from the leaf to the root.
+	/// Computing the path costs O(depth) hashes.
+	pub fn path(&self, param: &HashParam) -> Result<MembershipPath, MantaError> {
+		let depth = tree_depth(self.frontier.size);
+		let empty = &self.frontier.empty;
+		let mut path = Vec::with_capacity(PATH_LENGTH);
+		let mut node = hash_leaf(param, &self.asset.utxo)?;
+		for level in 0..PATH_LENGTH {
+			let pair = if level >= depth {
+				(node, empty[0])
+			} else if let Some(left) = self.left[level] {
+				(left, node)
+			} else {
+				(node, self.right[level].unwrap_or(empty[level]))
+			};
+			node = hash_inner_node(param, &pair.0, &pair.1)?;
+			path.push(pair);
+		}
+		Ok(MembershipPath { path })
+	}
+
+	/// The current root of the tree of the asset.
+	/// Computing the root costs O(depth) hashes.
+	pub fn root(&self, param: &HashParam) -> Result<LedgerMerkleTreeRoot, MantaError> {
+		self.frontier.root(param)
+	}
+
+	/// The `SenderMetaData` of the asset against the current root of its
+	/// tree, to spend the asset with.
+	/// Building the metadata costs O(depth) hashes.
+	pub fn sender_metadata(&self, param: &HashParam) -> Result<SenderMetaData, MantaError> {
+		Ok(SenderMetaData {
+			asset: self.asset,
+			root: self.root(param)?,
+			membership: self.path(param)?,
+		})
+	}
+
+	/// Check that the path of the asset leads to the current root.
+	pub fn verify(&self, param: &HashParam) -> Result<bool, MantaError> {
+		self.path(param)?
+			.verify(param, &self.root(param)?, &self.asset.utxo)
+	}
+}
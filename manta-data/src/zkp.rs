@@ -17,215 +17,88 @@
 use super::*;
 use ark_ed_on_bls12_381::Fq;
 use ark_groth16::verify_proof;
-use ark_relations::r1cs::ToConstraintField;
 use ark_serialize::CanonicalDeserialize;
-use ark_std::vec::Vec;
 use manta_crypto::*;
 
 impl MantaZKPVerifier for PrivateTransferData {
 	type VerificationKey = VerificationKey;
+
 	/// This algorithm verifies the ZKP, given the verification key and the data.
 	fn verify(&self, transfer_key_bytes: &VerificationKey) -> bool {
-		let buf: &[u8] = transfer_key_bytes.data;
-		let vk = match Groth16Vk::deserialize_unchecked(buf) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let pvk = Groth16Pvk::from(vk);
-		let proof = match Groth16Proof::deserialize(self.proof.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let k_old_1 = match CommitmentOutput::deserialize(self.sender_1.k.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let k_old_2 = match CommitmentOutput::deserialize(self.sender_2.k.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let cm_new_1 = match CommitmentOutput::deserialize(self.receiver_1.cm.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let cm_new_2 = match CommitmentOutput::deserialize(self.receiver_2.cm.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let merkle_root_1 = match HashOutput::deserialize(self.sender_1.root.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let merkle_root_2 = match HashOutput::deserialize(self.sender_2.root.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-
-		let mut inputs = [
-			k_old_1.x, k_old_1.y, // sender coin 1
-			k_old_2.x, k_old_2.y, // sender coin 2
-			cm_new_1.x, cm_new_1.y, // receiver coin 1
-			cm_new_2.x, cm_new_2.y, // receiver coin 2
-		]
-		.to_vec();
-		let sn_1: Vec<Fq> =
-			match ToConstraintField::<Fq>::to_field_elements(self.sender_1.void_number.as_ref()) {
-				Some(p) => p,
-				None => {
-					return false;
-				}
-			};
-		let sn_2: Vec<Fq> =
-			match ToConstraintField::<Fq>::to_field_elements(self.sender_2.void_number.as_ref()) {
-				Some(p) => p,
-				None => {
-					return false;
-				}
-			};
-
-		let mr_1: Vec<Fq> = match ToConstraintField::<Fq>::to_field_elements(&merkle_root_1) {
-			Some(p) => p,
-			None => {
-				return false;
-			}
-		};
-		let mr_2: Vec<Fq> = match ToConstraintField::<Fq>::to_field_elements(&merkle_root_2) {
-			Some(p) => p,
-			None => {
-				return false;
-			}
-		};
-		inputs = [
-			inputs[..].as_ref(),
-			sn_1.as_ref(),
-			sn_2.as_ref(),
-			mr_1.as_ref(),
-			mr_2.as_ref(),
-		]
-		.concat();
-
-		match verify_proof(&pvk, &proof, &inputs[..]) {
-			Ok(p) => p,
-			Err(_e) => false,
-		}
+		self.try_verify(transfer_key_bytes).is_ok()
 	}
 }
 
-impl MantaZKPVerifier for ReclaimData {
+impl<AccountId: ReclaimAccount> MantaZKPVerifier for ReclaimData<AccountId> {
 	type VerificationKey = VerificationKey;
 
 	/// This algorithm verifies the ZKP, given the verification key and the data.
 	fn verify(&self, reclaim_key_bytes: &VerificationKey) -> bool {
-		let buf: &[u8] = reclaim_key_bytes.data;
-		let vk = match Groth16Vk::deserialize_unchecked(buf) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let pvk = Groth16Pvk::from(vk);
-		let proof = match Groth16Proof::deserialize(self.proof.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let k_old_1 = match CommitmentOutput::deserialize(self.sender_1.k.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let k_old_2 = match CommitmentOutput::deserialize(self.sender_2.k.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let cm_new = match CommitmentOutput::deserialize(self.receiver.cm.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let merkle_root_1 = match HashOutput::deserialize(self.sender_1.root.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
-		let merkle_root_2 = match HashOutput::deserialize(self.sender_2.root.as_ref()) {
-			Ok(p) => p,
-			Err(_e) => {
-				return false;
-			}
-		};
+		self.try_verify(reclaim_key_bytes).is_ok()
+	}
+}
+
+impl PrivateTransferData {
+	/// This algorithm verifies the ZKP, given the verification key and the data,
+	/// and reports the step of the verification that failed.
+	/// The key is for the circuit of `PayloadVersion::CURRENT`.
+	/// With the `mock-proofs` feature, `MOCK_VERIFICATION_KEY` checks the
+	/// marker proof of the data instead.
+	pub fn try_verify(
+		&self,
+		transfer_key_bytes: &VerificationKey,
+	) -> Result<(), VerificationError> {
+		self.try_verify_version(transfer_key_bytes, PayloadVersion::CURRENT)
+	}
 
-		let mut inputs = [
-			k_old_1.x, k_old_1.y, // sender coin 1
-			k_old_2.x, k_old_2.y, // sender coin 2
-			cm_new.x, cm_new.y, // receiver coin
-		]
-		.to_vec();
-		let sn_1: Vec<Fq> =
-			match ToConstraintField::<Fq>::to_field_elements(self.sender_1.void_number.as_ref()) {
-				Some(p) => p,
-				None => {
-					return false;
-				}
-			};
-		let sn_2: Vec<Fq> =
-			match ToConstraintField::<Fq>::to_field_elements(self.sender_2.void_number.as_ref()) {
-				Some(p) => p,
-				None => {
-					return false;
-				}
-			};
+	/// This algorithm verifies the ZKP, given the verification key of the
+	/// circuit of `version` and the data, as for `try_verify`; e.g., the
+	/// published `TRANSFER_PK` verifies the transfers of `PayloadVersion::V0`.
+	pub fn try_verify_version(
+		&self,
+		transfer_key_bytes: &VerificationKey,
+		version: PayloadVersion,
+	) -> Result<(), VerificationError> {
+		PreparedVerifier::new_unchecked(transfer_key_bytes, version)?.verify_transfer(self)
+	}
+}
+
+impl<AccountId: ReclaimAccount> ReclaimData<AccountId> {
+	/// This algorithm verifies the ZKP, given the verification key and the data,
+	/// and reports the step of the verification that failed.
+	/// The key is for the circuit of `PayloadVersion::CURRENT`.
+	/// With the `mock-proofs` feature, `MOCK_VERIFICATION_KEY` checks the
+	/// marker proof of the data instead.
+	pub fn try_verify(&self, reclaim_key_bytes: &VerificationKey) -> Result<(), VerificationError> {
+		self.try_verify_version(reclaim_key_bytes, PayloadVersion::CURRENT)
+	}
 
-		let mr_1: Vec<Fq> = match ToConstraintField::<Fq>::to_field_elements(&merkle_root_1) {
-			Some(p) => p,
-			None => {
-				return false;
-			}
-		};
-		let mr_2: Vec<Fq> = match ToConstraintField::<Fq>::to_field_elements(&merkle_root_2) {
-			Some(p) => p,
-			None => {
-				return false;
-			}
-		};
-		let value_fq = Fq::from(self.reclaim_amount);
-		let asset_id_fq = Fq::from(self.asset_id as u64);
-		inputs = [
-			inputs[..].as_ref(),
-			sn_1.as_ref(),
-			sn_2.as_ref(),
-			mr_1.as_ref(),
-			mr_2.as_ref(),
-			[value_fq].as_ref(),
-			[asset_id_fq].as_ref(),
-		]
-		.concat();
+	/// This algorithm verifies the ZKP, given the verification key of the
+	/// circuit of `version` and the data, as for `try_verify`; e.g., the
+	/// published `RECLAIM_PK` verifies the reclaims of `PayloadVersion::V0`.
+	pub fn try_verify_version(
+		&self,
+		reclaim_key_bytes: &VerificationKey,
+		version: PayloadVersion,
+	) -> Result<(), VerificationError> {
+		PreparedVerifier::new_unchecked(reclaim_key_bytes, version)?.verify_reclaim(self)
+	}
+}
+
+/// Deserialize a proof.
+pub(crate) fn deserialize_proof(proof: &[u8; 192]) -> Result<Groth16Proof, VerificationError> {
+	Groth16Proof::deserialize(proof.as_ref()).map_err(|_| VerificationError::InvalidProof)
+}
 
-		match verify_proof(&pvk, &proof, &inputs[..]) {
-			Ok(p) => p,
-			Err(_e) => false,
-		}
+/// Run the pairing check of a proof against its public inputs.
+pub(crate) fn check_proof(
+	pvk: &Groth16Pvk,
+	proof: &Groth16Proof,
+	inputs: &[Fq],
+) -> Result<(), VerificationError> {
+	match verify_proof(pvk, proof, inputs) {
+		Ok(true) => Ok(()),
+		Ok(false) => Err(VerificationError::ProofRejected),
+		Err(_e) => Err(VerificationError::PairingError),
 	}
 }
@@ -0,0 +1,145 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements a checked decoding path for the ledger.
+//! The derived `Decode` trusts the encoded roots; a snapshot that is
+//! loaded from an untrusted source should go through
+//! `MantaPrivateAssetLedger::decode_and_verify` instead.
+//!
+//! The encoding of the ledger changed when trees got a capacity and
+//! shards a list of retired roots: a snapshot in the previous encoding,
+//! i.e., 256 shards of a list and a root, does not decode as a ledger.
+//! Such a snapshot is migrated with
+//! `MantaPrivateAssetLedger::decode_legacy_and_verify`.
+
+use super::*;
+use codec::Input;
+
+impl MantaPrivateAssetLedger {
+	/// Decode a ledger from a SCALE encoded snapshot, and check that
+	/// the decoded ledger satisfies all the ledger invariants.
+	pub fn decode_and_verify<I: Input>(
+		input: &mut I,
+		param: &HashParam,
+	) -> Result<Self, LedgerError> {
+		let ledger = Self::decode(input)?;
+		ledger.verify(param)?;
+		Ok(ledger)
+	}
+
+	/// Decode a ledger from a SCALE encoded snapshot in the legacy encoding,
+	/// and migrate it to a ledger of `SHARD_CAPACITY` that rolls over full
+	/// shards. The migrated ledger is checked as in `decode_and_verify`.
+	pub fn decode_legacy_and_verify<I: Input>(
+		input: &mut I,
+		param: &HashParam,
+	) -> Result<Self, LedgerError> {
+		let mut shards = Vec::with_capacity(256);
+		for _ in 0..256 {
+			let legacy = LegacyShard::decode(input)?;
+			shards.push(Shard::from_raw_parts(legacy.list, legacy.root, Vec::new()));
+		}
+
+		let ledger = Self::from_raw_shards(
+			shards
+				.try_into()
+				.unwrap_or_else(|_| panic!("The length is 256; qed")),
+			SHARD_CAPACITY,
+			FullShardPolicy::Rollover,
+		);
+		ledger.verify(param)?;
+		Ok(ledger)
+	}
+
+	/// Check that
+	/// - the tree capacity is valid;
+	/// - every commitment is stored in the shard it is assigned to;
+	/// - no commitment appears twice in the ledger;
+	/// - every shard holds one tree per retired root, plus the current tree;
+	/// - the roots of every shard are the merkle roots of its trees.
+	pub fn verify(&self, param: &HashParam) -> Result<(), LedgerError> {
+		if self.capacity == 0 || self.capacity > SHARD_CAPACITY {
+			return Err(LedgerError::InvalidCapacity(self.capacity));
+		}
+		for (shard_index, shard) in self.shard.iter().enumerate() {
+			shard.verify(shard_index, self.capacity, param)?;
+		}
+		Ok(())
+	}
+}
+
+/// A shard in the legacy encoding: a list of commitments and its root.
+#[derive(Encode, Decode)]
+pub(crate) struct LegacyShard {
+	pub(crate) list: Vec<[u8; 32]>,
+	pub(crate) root: [u8; 32],
+}
+
+impl Shard {
+	/// Check the invariants of the shard at position `shard_index`.
+	/// Since every commitment is checked to be in its own shard,
+	/// uniqueness only needs to be checked within a shard.
+	fn verify(
+		&self,
+		shard_index: usize,
+		capacity: u64,
+		param: &HashParam,
+	) -> Result<(), LedgerError> {
+		let mut seen = BTreeSet::new();
+		for (index, cm) in self.list.iter().enumerate() {
+			if shard_index_of(cm) != shard_index {
+				return Err(LedgerError::WrongShard {
+					shard: shard_index,
+					index,
+				});
+			}
+			if !seen.insert(cm) {
+				return Err(LedgerError::DuplicateCommitment {
+					shard: shard_index,
+					index,
+				});
+			}
+		}
+
+		// every retired tree is full, and the current tree is not over capacity
+		let capacity = capacity as usize;
+		let retired = self.epoch() * capacity;
+		if self.list.len() < retired || self.list.len() - retired > capacity {
+			return Err(LedgerError::MalformedShard { shard: shard_index });
+		}
+
+		for (tree, root) in self.list[..retired]
+			.chunks(capacity)
+			.zip(self.retired_roots.iter())
+		{
+			if <MantaCrypto as MerkleTree>::root(param.clone(), tree)? != *root {
+				return Err(LedgerError::RootMismatch { shard: shard_index });
+			}
+		}
+
+		// an empty tree carries the default root
+		let tree = &self.list[retired..];
+		let root = if tree.is_empty() {
+			[0u8; 32]
+		} else {
+			<MantaCrypto as MerkleTree>::root(param.clone(), tree)?
+		};
+		if root != self.root {
+			return Err(LedgerError::RootMismatch { shard: shard_index });
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,70 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module defines the errors raised by the private asset ledger.
+
+use manta_error::MantaError;
+
+/// An error that is raised when the ledger, or an encoded snapshot
+/// of the ledger, violates one of the ledger invariants.
+#[derive(Debug)]
+pub enum LedgerError {
+	/// The snapshot is not a valid SCALE encoding of a ledger.
+	Decode(codec::Error),
+	/// The stored root of a shard does not match the root of its list.
+	RootMismatch { shard: usize },
+	/// A commitment is stored in a shard other than the one it is assigned to.
+	WrongShard { shard: usize, index: usize },
+	/// A commitment appears more than once in the ledger.
+	DuplicateCommitment { shard: usize, index: usize },
+	/// The current tree of a shard is full, and the ledger rejects
+	/// new commitments for full shards.
+	ShardFull { shard: usize },
+	/// The number of commitments of a shard does not match its number of trees.
+	MalformedShard { shard: usize },
+	/// A delta or a checkpoint does not follow the current state of a shard.
+	OutOfSync { shard: usize },
+	/// A transaction spends against a root that is not in the ledger.
+	UnknownRoot,
+	/// The tree capacity is zero, or exceeds `SHARD_CAPACITY`.
+	InvalidCapacity(u64),
+	/// An error from the underlying cryptographic primitives.
+	Crypto(MantaError),
+}
+
+impl From<MantaError> for LedgerError {
+	fn from(e: MantaError) -> Self {
+		LedgerError::Crypto(e)
+	}
+}
+
+/// The error of `LedgerSharding::update`, which predates `LedgerError`:
+/// an error of the cryptographic primitives is passed through, and a
+/// commitment that the ledger rejects is a failed sanity check.
+impl From<LedgerError> for MantaError {
+	fn from(e: LedgerError) -> Self {
+		match e {
+			LedgerError::Crypto(e) => e,
+			_ => MantaError::SanityCheckFail,
+		}
+	}
+}
+
+impl From<codec::Error> for LedgerError {
+	fn from(e: codec::Error) -> Self {
+		LedgerError::Decode(e)
+	}
+}
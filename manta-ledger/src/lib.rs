@@ -19,24 +19,224 @@
 //! Each tree is a `Shard`, and collectively they form the `Shards`.
 //! When an UTXO is posted to the ledger, it will be send to the corresponding
 //! shard via some deterministic fashion.
+//! A merkle tree has a fixed capacity; when the tree of a shard is full,
+//! the ledger either rejects new commitments for that shard, or starts
+//! a new tree in the shard, as specified by its `FullShardPolicy`.
 
+mod checked;
+mod error;
+mod sync;
+
+use ark_std::collections::BTreeSet;
+use ark_std::convert::TryInto;
 use ark_std::vec::Vec;
 use codec::{Decode, Encode};
 use manta_crypto::*;
 use manta_error::MantaError;
-use ark_std::convert::TryInto;
+
+pub use error::LedgerError;
+pub use sync::{Checkpoint, LedgerDelta, LedgerLeaf, ShardDelta};
+#[cfg(test)]
+mod test;
+
+/// The number of leaves that a shard merkle tree can hold.
+/// This is the capacity of a `LedgerMerkleTree` of fixed height.
+pub const SHARD_CAPACITY: u64 = 1 << (LedgerMerkleTree::HEIGHT as u64 - 1);
 
 /// A shard is a list of commitment, and a merkle root of this list.
+/// The fields are private so that the root is always kept in sync
+/// with the list; a shard is only mutated through `LedgerSharding`.
+///
+/// The list is split into consecutive merkle trees of `capacity` leaves.
+/// Once a tree is full, it may be retired: its root is kept in
+/// `retired_roots` and a new tree starts with the next commitment.
+/// `root` is the root of the current (i.e., last) tree.
 #[derive(Encode, Debug, Decode, Clone, Default, PartialEq)]
 pub struct Shard {
-	pub list: Vec<[u8; 32]>,
-	pub root: [u8; 32],
+	list: Vec<[u8; 32]>,
+	root: [u8; 32],
+	retired_roots: Vec<[u8; 32]>,
+}
+
+impl Shard {
+	/// Build a shard from its raw parts, without checking that
+	/// the roots are the merkle roots of `list`.
+	/// This is meant for migrations; the resulting ledger should be
+	/// checked with `MantaPrivateAssetLedger::verify`.
+	pub fn from_raw_parts(
+		list: Vec<[u8; 32]>,
+		root: [u8; 32],
+		retired_roots: Vec<[u8; 32]>,
+	) -> Self {
+		Self {
+			list,
+			root,
+			retired_roots,
+		}
+	}
+
+	/// The commitments stored in this shard, in insertion order.
+	pub fn list(&self) -> &[[u8; 32]] {
+		&self.list
+	}
+
+	/// The merkle root of the current tree of this shard.
+	pub fn root(&self) -> &[u8; 32] {
+		&self.root
+	}
+
+	/// The merkle roots of the retired trees of this shard, oldest first.
+	pub fn retired_roots(&self) -> &[[u8; 32]] {
+		&self.retired_roots
+	}
+
+	/// The epoch of the current tree, i.e., the number of retired trees.
+	pub fn epoch(&self) -> usize {
+		self.retired_roots.len()
+	}
+
+	/// The number of commitments stored in this shard.
+	pub fn len(&self) -> usize {
+		self.list.len()
+	}
+
+	/// Whether this shard stores no commitment.
+	pub fn is_empty(&self) -> bool {
+		self.list.is_empty()
+	}
+
+	/// Decompose the shard into its raw parts.
+	pub fn into_raw_parts(self) -> (Vec<[u8; 32]>, [u8; 32], Vec<[u8; 32]>) {
+		(self.list, self.root, self.retired_roots)
+	}
+
+	/// The leaves of the current tree, given the capacity of a tree.
+	/// A malformed shard, with more retired roots than full trees, has no
+	/// current tree; `MantaPrivateAssetLedger::verify` reports it.
+	fn current_tree(&self, capacity: u64) -> &[[u8; 32]] {
+		self.epoch()
+			.checked_mul(capacity as usize)
+			.and_then(|start| self.list.get(start..))
+			.unwrap_or(&[])
+	}
+}
+
+/// What the ledger does with a commitment that is sent to a full shard.
+#[derive(Encode, Debug, Decode, Clone, Copy, PartialEq)]
+pub enum FullShardPolicy {
+	/// The commitment is rejected with `LedgerError::ShardFull`.
+	Reject,
+	/// The full tree is retired, its root remains valid for `check_root`,
+	/// and the commitment is the first leaf of a new tree in the shard.
+	Rollover,
 }
 
 /// A Shards is a list of Shard-s.
 #[derive(Encode, Debug, Decode, Clone, PartialEq)]
 pub struct MantaPrivateAssetLedger {
-	pub shard: [Shard; 256],
+	shard: [Shard; 256],
+	capacity: u64,
+	policy: FullShardPolicy,
+}
+
+impl MantaPrivateAssetLedger {
+	/// Build an empty ledger whose trees hold at most `capacity` leaves.
+	/// The capacity cannot exceed `SHARD_CAPACITY`.
+	pub fn new(capacity: u64, policy: FullShardPolicy) -> Result<Self, LedgerError> {
+		if capacity == 0 || capacity > SHARD_CAPACITY {
+			return Err(LedgerError::InvalidCapacity(capacity));
+		}
+		let shard = ark_std::vec![Shard::default(); 256];
+
+		Ok(Self {
+			shard: shard
+				.try_into()
+				.unwrap_or_else(|_| panic!("The length is 256; qed")),
+			capacity,
+			policy,
+		})
+	}
+
+	/// Build a ledger from raw shards, without checking the ledger invariants.
+	/// This is meant for migrations; the resulting ledger should be
+	/// checked with `MantaPrivateAssetLedger::verify`.
+	pub fn from_raw_shards(shard: [Shard; 256], capacity: u64, policy: FullShardPolicy) -> Self {
+		Self {
+			shard,
+			capacity,
+			policy,
+		}
+	}
+
+	/// The shard at position `index`.
+	pub fn shard(&self, index: u8) -> &Shard {
+		&self.shard[index as usize]
+	}
+
+	/// All the shards of the ledger.
+	pub fn shards(&self) -> &[Shard; 256] {
+		&self.shard
+	}
+
+	/// The number of leaves a tree of a shard holds.
+	pub fn capacity(&self) -> u64 {
+		self.capacity
+	}
+
+	/// The policy applied to full shards.
+	pub fn policy(&self) -> FullShardPolicy {
+		self.policy
+	}
+
+	/// The leaves of the current tree of the shard at position `index`.
+	/// These are the leaves to build a `SenderMetaData` against.
+	pub fn current_tree(&self, index: u8) -> &[[u8; 32]] {
+		self.shard[index as usize].current_tree(self.capacity)
+	}
+
+	/// Update the shards with all the commitments of `targets`, in order.
+	/// This is atomic: if a commitment is rejected, the shards are left
+	/// as they were before the call.
+	pub fn update_all(
+		&mut self,
+		targets: &[[u8; 32]],
+		param: &HashParam,
+	) -> Result<(), LedgerError> {
+		let backup = self.backup(targets.iter().map(shard_index_of));
+		for cm in targets {
+			if let Err(e) = self.try_update(cm, param) {
+				self.restore(backup);
+				return Err(e);
+			}
+		}
+		Ok(())
+	}
+
+	/// A copy of each of the shards at positions `indices`, taken once
+	/// per shard, to `restore` them if an update is rejected midway.
+	fn backup<I: Iterator<Item = usize>>(&self, indices: I) -> Vec<(usize, Shard)> {
+		indices
+			.collect::<BTreeSet<usize>>()
+			.into_iter()
+			.map(|index| (index, self.shard[index].clone()))
+			.collect()
+	}
+
+	/// Put back the shards of a `backup`.
+	fn restore(&mut self, backup: Vec<(usize, Shard)>) {
+		for (index, shard) in backup {
+			self.shard[index] = shard;
+		}
+	}
+}
+
+/// The index of the shard that a commitment is assigned to.
+pub fn shard_index_of(cm: &[u8; 32]) -> usize {
+	// FIXME: at the moment, the index of the shard is determined by the first
+	// byte of the cm. this may be potentially risky, since the commitment
+	// is a group element, and the first byte may not be uniformly distributed
+	// between 0 and 255.
+	cm[0] as usize
 }
 
 pub trait LedgerSharding {
@@ -50,7 +250,8 @@ pub trait LedgerSharding {
 	/// the commitment exists in the current shards
 	fn exist(&self, target: &Self::Commitment) -> bool;
 
-	/// update the shards with a new commitment
+	/// update the shards with a new commitment;
+	/// a commitment that already exists in the shards is rejected
 	fn update(&mut self, target: &Self::Commitment, param: Self::Param) -> Result<(), MantaError>;
 }
 
@@ -59,47 +260,83 @@ impl LedgerSharding for MantaPrivateAssetLedger {
 	type Root = [u8; 32];
 	type Param = HashParam;
 
-	// root exists in the current shards
+	// root exists in the current shards,
+	// either as the root of a current tree or of a retired one
 	fn check_root(&self, target: &Self::Root) -> bool {
-		self.shard.iter().any(|s| s.root == *target)
+		self.shard
+			.iter()
+			.any(|s| s.root == *target || s.retired_roots.contains(target))
 	}
 
 	fn exist(&self, target: &Self::Commitment) -> bool {
-		// FIXME: at the moment, the index of the shard is determined by the first
-		// byte of the cm. this may be potentially risky, since the commitment
-		// is a group element, and the first byte may not be uniformly distributed
-		// between 0 and 255.
-		let shard_index = target[0] as usize;
+		let shard_index = shard_index_of(target);
 
 		self.shard[shard_index].list.iter().any(|e| e == target)
 	}
 
 	// this function updates the ledger shards,
-	// this function does not check if target already exists in the list or not
+	// and fails if target already exists in the list;
+	// see `MantaPrivateAssetLedger::try_update` for the detailed error
 	fn update(&mut self, target: &Self::Commitment, param: Self::Param) -> Result<(), MantaError> {
-		// FIXME: at the moment, the index of the shard is determined by the first
-		// byte of the cm. this may be potentially risky, since the commitment
-		// is a group element, and the first byte may not be uniformly distributed
-		// between 0 and 255.
-		let shard_index = target[0] as usize;
+		self.try_update(target, &param).map_err(|e| e.into())
+	}
+}
+
+impl MantaPrivateAssetLedger {
+	/// Update the shards with a new commitment;
+	/// a commitment that already exists in the shards is rejected.
+	/// This is `LedgerSharding::update`, with a detailed error.
+	pub fn try_update(&mut self, target: &[u8; 32], param: &HashParam) -> Result<(), LedgerError> {
+		let shard_index = shard_index_of(target);
+
+		if let Some(index) = self.shard[shard_index]
+			.list
+			.iter()
+			.position(|e| e == target)
+		{
+			return Err(LedgerError::DuplicateCommitment {
+				shard: shard_index,
+				index,
+			});
+		}
+		self.update_unchecked(target, param)
+	}
+
+	/// Update the shards with a new commitment, without checking if
+	/// it already exists in the shards; only use this for trusted bulk loads.
+	pub fn update_unchecked(
+		&mut self,
+		target: &[u8; 32],
+		param: &HashParam,
+	) -> Result<(), LedgerError> {
+		let shard_index = shard_index_of(target);
+		let capacity = self.capacity;
+		let shard = &mut self.shard[shard_index];
+
+		// make room for the target if the current tree is full
+		if shard.current_tree(capacity).len() as u64 >= capacity {
+			match self.policy {
+				FullShardPolicy::Reject => {
+					return Err(LedgerError::ShardFull { shard: shard_index });
+				}
+				FullShardPolicy::Rollover => {
+					shard.retired_roots.push(shard.root);
+					shard.root = [0u8; 32];
+				}
+			}
+		}
 
 		// update the list, and the root accordingly
-		self.shard[shard_index].list.push(*target);
-		self.shard[shard_index].root =
-			<MantaCrypto as MerkleTree>::root(param, &self.shard[shard_index].list)?;
+		shard.list.push(*target);
+		shard.root =
+			<MantaCrypto as MerkleTree>::root(param.clone(), shard.current_tree(capacity))?;
 		Ok(())
 	}
 }
 
 impl Default for MantaPrivateAssetLedger {
 	fn default() -> Self {
-		
-		let shard = ark_std::vec![Shard::default(); 256];
-
-		Self {
-			shard: shard
-				.try_into()
-				.unwrap_or_else(|_| panic!("The length is 256; qed")),
-		}
+		Self::new(SHARD_CAPACITY, FullShardPolicy::Rollover)
+			.unwrap_or_else(|_| panic!("SHARD_CAPACITY is a valid capacity; qed"))
 	}
 }
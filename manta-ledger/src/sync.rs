@@ -0,0 +1,194 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This module implements the pagination and sync API of the ledger.
+//! A node serves the commitments a wallet has not seen yet as a
+//! `LedgerDelta`, and the wallet keeps track of what it has seen
+//! with a `Checkpoint`.
+
+use super::*;
+
+/// A leaf of the ledger: the index of the shard, the index of the
+/// commitment within the shard, and the commitment itself.
+pub type LedgerLeaf = (u8, u64, [u8; 32]);
+
+/// A checkpoint is the number of commitments in every shard of the
+/// ledger at some point in time.
+#[derive(Encode, Debug, Decode, Clone, PartialEq)]
+pub struct Checkpoint {
+	lengths: [u64; 256],
+}
+
+impl Default for Checkpoint {
+	fn default() -> Self {
+		Self {
+			lengths: [0u64; 256],
+		}
+	}
+}
+
+impl Checkpoint {
+	/// The number of commitments in the shard at position `index`.
+	pub fn len(&self, index: u8) -> u64 {
+		self.lengths[index as usize]
+	}
+
+	/// Advance the checkpoint past the commitments of `delta`.
+	/// The delta must start exactly at this checkpoint; otherwise, the
+	/// checkpoint is left as it was before the call.
+	pub fn apply(&mut self, delta: &LedgerDelta) -> Result<(), LedgerError> {
+		let mut lengths = self.lengths;
+		for shard_delta in delta.shards.iter() {
+			let shard_index = shard_delta.shard as usize;
+			if lengths[shard_index] != shard_delta.start {
+				return Err(LedgerError::OutOfSync { shard: shard_index });
+			}
+			lengths[shard_index] += shard_delta.commitments.len() as u64;
+		}
+		self.lengths = lengths;
+		Ok(())
+	}
+}
+
+/// The new commitments of a shard, starting at position `start`.
+#[derive(Encode, Debug, Decode, Clone, Default, PartialEq)]
+pub struct ShardDelta {
+	pub shard: u8,
+	#[codec(compact)]
+	pub start: u64,
+	pub commitments: Vec<[u8; 32]>,
+}
+
+/// The new commitments of the ledger since some `Checkpoint`;
+/// shards without new commitments are omitted.
+#[derive(Encode, Debug, Decode, Clone, Default, PartialEq)]
+pub struct LedgerDelta {
+	pub shards: Vec<ShardDelta>,
+}
+
+impl LedgerDelta {
+	/// Whether the delta carries no commitment.
+	pub fn is_empty(&self) -> bool {
+		self.shards.iter().all(|s| s.commitments.is_empty())
+	}
+
+	/// Iterate over the leaves of the delta.
+	pub fn iter(&self) -> impl Iterator<Item = LedgerLeaf> + '_ {
+		self.shards.iter().flat_map(|s| {
+			s.commitments
+				.iter()
+				.enumerate()
+				.map(move |(i, cm)| (s.shard, s.start + i as u64, *cm))
+		})
+	}
+}
+
+impl MantaPrivateAssetLedger {
+	/// The checkpoint of the current state of the ledger.
+	pub fn checkpoint(&self) -> Checkpoint {
+		let mut checkpoint = Checkpoint::default();
+		for (length, shard) in checkpoint.lengths.iter_mut().zip(self.shard.iter()) {
+			*length = shard.len() as u64;
+		}
+		checkpoint
+	}
+
+	/// Iterate over all the leaves of the ledger, shard by shard.
+	pub fn iter(&self) -> impl Iterator<Item = LedgerLeaf> + '_ {
+		(0..=255u8).flat_map(move |index| self.shard_range(index, 0))
+	}
+
+	/// Iterate over the leaves of the shard at position `index`,
+	/// starting at the leaf at position `start`.
+	pub fn shard_range(&self, index: u8, start: u64) -> impl Iterator<Item = LedgerLeaf> + '_ {
+		let list = self.shard[index as usize].list();
+		let start = (start as usize).min(list.len());
+		list[start..]
+			.iter()
+			.enumerate()
+			.map(move |(i, cm)| (index, (start + i) as u64, *cm))
+	}
+
+	/// Iterate over the leaves that were posted after `checkpoint`.
+	pub fn since<'a>(
+		&'a self,
+		checkpoint: &'a Checkpoint,
+	) -> impl Iterator<Item = LedgerLeaf> + 'a {
+		(0..=255u8).flat_map(move |index| self.shard_range(index, checkpoint.len(index)))
+	}
+
+	/// The delta between `checkpoint` and the current state of the ledger.
+	/// Fails if the checkpoint is ahead of the ledger.
+	pub fn delta_since(&self, checkpoint: &Checkpoint) -> Result<LedgerDelta, LedgerError> {
+		let mut delta = LedgerDelta::default();
+		for (index, shard) in self.shard.iter().enumerate() {
+			let start = checkpoint.lengths[index];
+			if start > shard.len() as u64 {
+				return Err(LedgerError::OutOfSync { shard: index });
+			}
+			if start < shard.len() as u64 {
+				delta.shards.push(ShardDelta {
+					shard: index as u8,
+					start,
+					commitments: shard.list()[start as usize..].to_vec(),
+				});
+			}
+		}
+		Ok(delta)
+	}
+
+	/// Apply a delta served by another node to this ledger.
+	/// The delta must start exactly at the current state of the ledger,
+	/// and its commitments go through the same checks as `update`.
+	/// This is atomic: if the delta is rejected, the ledger is left
+	/// as it was before the call.
+	pub fn apply_delta(
+		&mut self,
+		delta: &LedgerDelta,
+		param: &HashParam,
+	) -> Result<(), LedgerError> {
+		let backup = self.backup(delta.shards.iter().map(|d| d.shard as usize));
+		if let Err(e) = self.apply_shard_deltas(delta, param) {
+			self.restore(backup);
+			return Err(e);
+		}
+		Ok(())
+	}
+
+	/// Apply the shard deltas of `delta` in order, stopping at the first error.
+	fn apply_shard_deltas(
+		&mut self,
+		delta: &LedgerDelta,
+		param: &HashParam,
+	) -> Result<(), LedgerError> {
+		for shard_delta in delta.shards.iter() {
+			let shard_index = shard_delta.shard as usize;
+			if self.shard[shard_index].len() as u64 != shard_delta.start {
+				return Err(LedgerError::OutOfSync { shard: shard_index });
+			}
+			for (i, cm) in shard_delta.commitments.iter().enumerate() {
+				if shard_index_of(cm) != shard_index {
+					return Err(LedgerError::WrongShard {
+						shard: shard_index,
+						index: shard_delta.start as usize + i,
+					});
+				}
+				self.try_update(cm, param)?;
+			}
+		}
+		Ok(())
+	}
+}
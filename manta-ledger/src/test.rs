@@ -0,0 +1,293 @@
+// Copyright 2019-2021 Manta Network.
+// This file is part of manta-types.
+//
+// manta-types is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// manta-types is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with manta-types.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use ark_std::rand::RngCore;
+use codec::Encode;
+
+fn random_ledger<R: RngCore>(
+	param: &HashParam,
+	size: usize,
+	rng: &mut R,
+) -> MantaPrivateAssetLedger {
+	let mut ledger = MantaPrivateAssetLedger::default();
+	for _ in 0..size {
+		let mut cm = [0u8; 32];
+		rng.fill_bytes(&mut cm);
+		ledger.update(&cm, param.clone()).unwrap();
+	}
+	ledger
+}
+
+#[test]
+fn test_shard_accessors() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut ledger = MantaPrivateAssetLedger::default();
+	assert!(ledger.shard(9).is_empty());
+
+	// the shard lists its commitments in insertion order,
+	// and its root is kept in sync with the list
+	let cms: Vec<[u8; 32]> = (0..3)
+		.map(|_| {
+			let mut cm = [0u8; 32];
+			rng.fill_bytes(&mut cm);
+			cm[0] = 9;
+			cm
+		})
+		.collect();
+	for cm in cms.iter() {
+		ledger.update(cm, hash_param.clone()).unwrap();
+	}
+	let shard = ledger.shard(9);
+	assert_eq!(shard.list(), cms.as_slice());
+	assert_eq!(shard.len(), 3);
+	assert_eq!(
+		*shard.root(),
+		<MantaCrypto as MerkleTree>::root(hash_param, &cms).unwrap()
+	);
+	assert_eq!(shard.epoch(), 0);
+
+	// a shard round trips through its raw parts
+	let (list, root, retired_roots) = shard.clone().into_raw_parts();
+	assert_eq!(Shard::from_raw_parts(list, root, retired_roots), *shard);
+}
+
+#[test]
+fn test_decode_and_verify() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let ledger = random_ledger(&hash_param, 10, &mut rng);
+
+	let buf = ledger.encode();
+	let ledger_recover =
+		MantaPrivateAssetLedger::decode_and_verify(&mut buf.as_ref(), &hash_param).unwrap();
+	assert_eq!(ledger, ledger_recover);
+}
+
+#[test]
+fn test_decode_and_verify_rejects_fabricated_root() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut ledger = random_ledger(&hash_param, 10, &mut rng);
+
+	let shard_index = ledger.shard.iter().position(|s| !s.is_empty()).unwrap();
+	rng.fill_bytes(&mut ledger.shard[shard_index].root);
+
+	let buf = ledger.encode();
+	match MantaPrivateAssetLedger::decode_and_verify(&mut buf.as_ref(), &hash_param) {
+		Err(LedgerError::RootMismatch { shard }) => assert_eq!(shard, shard_index),
+		_ => panic!("a fabricated root should be rejected"),
+	}
+}
+
+#[test]
+fn test_update_rejects_duplicate() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut ledger = MantaPrivateAssetLedger::default();
+
+	let mut cm = [0u8; 32];
+	rng.fill_bytes(&mut cm);
+	ledger.update(&cm, hash_param.clone()).unwrap();
+	match ledger.try_update(&cm, &hash_param) {
+		Err(LedgerError::DuplicateCommitment { shard, index }) => {
+			assert_eq!(shard, shard_index_of(&cm));
+			assert_eq!(index, 0);
+		}
+		_ => panic!("a duplicated commitment should be rejected"),
+	}
+	assert!(ledger.update(&cm, hash_param.clone()).is_err());
+
+	// a trusted bulk load skips the check
+	ledger.update_unchecked(&cm, &hash_param).unwrap();
+	assert_eq!(ledger.shard(cm[0]).len(), 2);
+}
+
+#[test]
+fn test_full_shard_policy() {
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	// three commitments that are assigned to the same shard
+	let cms: Vec<[u8; 32]> = (1..4u8)
+		.map(|i| [i; 32])
+		.map(|mut cm| {
+			cm[0] = 7;
+			cm
+		})
+		.collect();
+
+	let mut ledger = MantaPrivateAssetLedger::new(2, FullShardPolicy::Reject).unwrap();
+	ledger.update(&cms[0], hash_param.clone()).unwrap();
+	ledger.update(&cms[1], hash_param.clone()).unwrap();
+	match ledger.try_update(&cms[2], &hash_param) {
+		Err(LedgerError::ShardFull { shard }) => assert_eq!(shard, 7),
+		_ => panic!("a full shard should reject new commitments"),
+	}
+
+	let mut ledger = MantaPrivateAssetLedger::new(2, FullShardPolicy::Rollover).unwrap();
+	ledger.update(&cms[0], hash_param.clone()).unwrap();
+	ledger.update(&cms[1], hash_param.clone()).unwrap();
+	let full_root = *ledger.shard(7).root();
+	ledger.update(&cms[2], hash_param.clone()).unwrap();
+
+	assert_eq!(ledger.shard(7).epoch(), 1);
+	assert_eq!(ledger.current_tree(7), &cms[2..]);
+	assert!(ledger.check_root(&full_root));
+	assert!(ledger.exist(&cms[0]));
+	ledger.verify(&hash_param).unwrap();
+
+	// a shard with more retired roots than full trees has no current tree
+	let mut shards = ledger.shards().clone();
+	let (list, root, mut retired_roots) = shards[7].clone().into_raw_parts();
+	retired_roots.push(root);
+	retired_roots.push(root);
+	shards[7] = Shard::from_raw_parts(list, root, retired_roots);
+	let malformed = MantaPrivateAssetLedger::from_raw_shards(shards, 2, FullShardPolicy::Rollover);
+	assert!(malformed.current_tree(7).is_empty());
+	match malformed.verify(&hash_param) {
+		Err(LedgerError::MalformedShard { shard }) => assert_eq!(shard, 7),
+		_ => panic!("a shard without its retired trees should be rejected"),
+	}
+}
+
+#[test]
+fn test_delta_sync() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut node = random_ledger(&hash_param, 10, &mut rng);
+	let mut replica = node.clone();
+	let mut checkpoint = node.checkpoint();
+
+	for _ in 0..10 {
+		let mut cm = [0u8; 32];
+		rng.fill_bytes(&mut cm);
+		node.update(&cm, hash_param.clone()).unwrap();
+	}
+	let new_leaves: Vec<LedgerLeaf> = node.since(&checkpoint).collect();
+	assert_eq!(new_leaves.len(), 10);
+
+	// the delta goes through the wire as SCALE
+	let buf = node.delta_since(&checkpoint).unwrap().encode();
+	let delta = LedgerDelta::decode(&mut buf.as_ref()).unwrap();
+	assert_eq!(delta.iter().collect::<Vec<_>>(), new_leaves);
+
+	replica.apply_delta(&delta, &hash_param).unwrap();
+	checkpoint.apply(&delta).unwrap();
+	assert_eq!(replica, node);
+	assert_eq!(checkpoint, node.checkpoint());
+
+	// a delta cannot be applied twice
+	assert!(checkpoint.apply(&delta).is_err());
+	assert_eq!(checkpoint, node.checkpoint());
+
+	// nor can a delta that is out of sync in a later shard only
+	let mut checkpoint = replica.checkpoint();
+	let mut delta = LedgerDelta::default();
+	for index in [1u8, 2u8].iter() {
+		let mut cm = [0u8; 32];
+		cm[0] = *index;
+		delta.shards.push(ShardDelta {
+			shard: *index,
+			start: checkpoint.len(*index),
+			commitments: ark_std::vec![cm],
+		});
+	}
+	delta.shards[1].start += 1;
+	match checkpoint.apply(&delta) {
+		Err(LedgerError::OutOfSync { shard }) => assert_eq!(shard, 2),
+		_ => panic!("a delta out of sync should be rejected"),
+	}
+	assert_eq!(checkpoint, replica.checkpoint());
+}
+
+#[test]
+fn test_update_all_is_atomic() {
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut ledger = MantaPrivateAssetLedger::default();
+	ledger.update(&[3u8; 32], hash_param.clone()).unwrap();
+	let snapshot = ledger.clone();
+
+	// the second commitment is a duplicate, so the first one is rolled back
+	match ledger.update_all(&[[5u8; 32], [3u8; 32]], &hash_param) {
+		Err(LedgerError::DuplicateCommitment { shard, .. }) => assert_eq!(shard, 3),
+		_ => panic!("a duplicated commitment should be rejected"),
+	}
+	assert_eq!(ledger, snapshot);
+
+	// a shard that is touched twice is restored too
+	let mut cm = [0u8; 32];
+	cm[0] = 5;
+	assert!(ledger
+		.update_all(&[[5u8; 32], cm, [3u8; 32]], &hash_param)
+		.is_err());
+	assert_eq!(ledger, snapshot);
+
+	ledger
+		.update_all(&[[5u8; 32], [6u8; 32]], &hash_param)
+		.unwrap();
+	assert!(ledger.exist(&[5u8; 32]) && ledger.exist(&[6u8; 32]));
+}
+
+#[test]
+fn test_decode_legacy_snapshot() {
+	let mut rng = ark_std::test_rng();
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let ledger = random_ledger(&hash_param, 10, &mut rng);
+
+	// a snapshot in the encoding that predates tree capacities
+	let mut buf = Vec::new();
+	for shard in ledger.shards().iter() {
+		checked::LegacyShard {
+			list: shard.list().to_vec(),
+			root: *shard.root(),
+		}
+		.encode_to(&mut buf);
+	}
+
+	assert!(MantaPrivateAssetLedger::decode_and_verify(&mut buf.as_ref(), &hash_param).is_err());
+	let migrated =
+		MantaPrivateAssetLedger::decode_legacy_and_verify(&mut buf.as_ref(), &hash_param).unwrap();
+	assert_eq!(migrated, ledger);
+}
+
+#[test]
+fn test_apply_delta_is_atomic() {
+	let hash_param = HashParam::deserialize(HASH_PARAM.data).unwrap();
+	let mut node = MantaPrivateAssetLedger::default();
+	let mut replica = node.clone();
+	let checkpoint = node.checkpoint();
+	for i in 1..4u8 {
+		node.update(&[i; 32], hash_param.clone()).unwrap();
+		let mut cm = [0u8; 32];
+		cm[0] = i;
+		node.update(&cm, hash_param.clone()).unwrap();
+	}
+
+	// the first leaf of the second shard is assigned to another shard,
+	// so the leaves of the first shard are rolled back
+	let mut delta = node.delta_since(&checkpoint).unwrap();
+	assert_eq!(delta.shards.len(), 3);
+	delta.shards[1].commitments[0] = [9u8; 32];
+	match replica.apply_delta(&delta, &hash_param) {
+		Err(LedgerError::WrongShard { shard, index }) => assert_eq!((shard, index), (2, 0)),
+		_ => panic!("a leaf of another shard should be rejected"),
+	}
+	assert_eq!(replica, MantaPrivateAssetLedger::default());
+
+	let delta = node.delta_since(&checkpoint).unwrap();
+	replica.apply_delta(&delta, &hash_param).unwrap();
+	assert_eq!(replica, node);
+}